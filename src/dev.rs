@@ -6,6 +6,7 @@ use bevy_editor_pls::prelude::*;
 // use bevy_xpbd_3d::prelude::*;
 
 pub(crate) mod dev_editor;
+mod physics_trace;
 
 /// Plugin with debugging utility intended for use during development only.
 /// Don't include this in a release build.
@@ -16,8 +17,10 @@ pub(super) fn plugin(app: &mut App) {
             .add_plugins((
                 FrameTimeDiagnosticsPlugin,
                 dev_editor::plugin,
+                physics_trace::plugin,
                 LogDiagnosticsPlugin::filtered(vec![]),
                 bevy_rapier3d::render::RapierDebugRenderPlugin::default(),
+                oxidized_navigation::debug_draw::OxidizedNavigationDebugDrawPlugin,
             ));
         // .insert_gizmo_group(
         //     PhysicsGizmos {
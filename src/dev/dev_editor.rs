@@ -1,13 +1,24 @@
-use crate::player_control::camera::ForceCursorGrabMode;
+use crate::file_system_interaction::replay::{
+    ReplayPlayback, ReplayRecorder, ReplayRequest, QUICK_REPLAY,
+};
+use crate::level_instantiation::on_spawn::{ColliderError, Player};
+use crate::movement::physics::{CollisionLayer, CollisionLayers, LayerMask};
+use crate::physics_time::{PhysicsTime, PhysicsTimeExt, RunPhysicsUntilExt, PHYSICS_DIAGNOSTICS};
+use crate::player_control::{actions::UiAction, camera::ForceCursorGrabMode};
 use crate::util::error;
 use anyhow::Context;
-use bevy::{prelude::*, window::CursorGrabMode};
+use bevy::{
+    diagnostic::DiagnosticsStore, prelude::*, render::primitives::Aabb, window::CursorGrabMode,
+};
 use bevy_editor_pls::{
     editor::{Editor, EditorEvent},
     editor_window::EditorWindow,
     AddEditorWindow,
 };
 use bevy_egui::egui;
+use bevy_tnua::controller::TnuaController;
+use leafwing_input_manager::prelude::ActionState;
+use oxidized_navigation::debug_draw::DrawNavMesh;
 // use bevy_xpbd_3d::prelude::PhysicsGizmos;
 use serde::{Deserialize, Serialize};
 
@@ -16,7 +27,13 @@ pub(super) fn plugin(app: &mut App) {
         .add_editor_window::<DevEditorWindow>()
         .add_systems(
             Update,
-            (handle_debug_render.pipe(error), set_cursor_grab_mode),
+            (
+                handle_debug_render.pipe(error),
+                handle_navmesh_render.pipe(error),
+                set_cursor_grab_mode,
+                handle_physics_time_actions,
+                highlight_collider_errors,
+            ),
         );
 }
 
@@ -27,7 +44,7 @@ impl EditorWindow for DevEditorWindow {
     const NAME: &'static str = "Foxtrot Dev";
     const DEFAULT_SIZE: (f32, f32) = (200., 150.);
     fn ui(
-        _world: &mut World,
+        world: &mut World,
         mut cx: bevy_editor_pls::editor_window::EditorWindowContext,
         ui: &mut egui::Ui,
     ) {
@@ -38,8 +55,163 @@ impl EditorWindow for DevEditorWindow {
         state.open = true;
         ui.heading("Debug Rendering");
         ui.checkbox(&mut state.collider_render_enabled, "Colliders");
-        ui.checkbox(&mut state.navmesh_render_enabled, "Navmeshes");
+        // Only the main world's navmesh can be drawn, the other profiles are built in sub apps
+        ui.checkbox(&mut state.navmesh_render_enabled, "Humanoid navmesh");
+
+        ui.heading("Physics Time");
+        show_physics_time_controls(world, ui);
+
+        ui.heading("Physics Diagnostics");
+        ui.checkbox(&mut state.physics_trace_enabled, "Write CSV trace");
+        show_physics_diagnostics(world, ui);
+
+        ui.heading("Replay");
+        show_replay_controls(world, ui);
+
+        ui.heading("Collision Layers");
+        show_collision_layers(world, ui);
+
+        ui.heading("Collider Errors");
+        show_collider_errors(world, ui);
+    }
+}
+
+fn show_physics_time_controls(world: &mut World, ui: &mut egui::Ui) {
+    let mut physics_time = world.resource_mut::<PhysicsTime>();
+    ui.label(format!("Mode: {:?}", physics_time.context().mode));
+    ui.horizontal(|ui| {
+        if physics_time.is_paused() {
+            if ui.button("Resume").clicked() {
+                physics_time.resume();
+            }
+        } else if ui.button("Pause").clicked() {
+            physics_time.pause();
+        }
+        if ui.button("Step").clicked() {
+            physics_time.step(1);
+        }
+        if ui.button("Step 10").clicked() {
+            physics_time.step(10);
+        }
+    });
+    let mut speed = physics_time.speed();
+    let slider = egui::Slider::new(&mut speed, 0.05..=4.0)
+        .logarithmic(true)
+        .text("Speed");
+    if ui.add(slider).changed() {
+        physics_time.set_speed(speed);
+    }
+    let speed = physics_time.speed();
+    if ui.button("Run until the player lands").clicked() {
+        world.run_physics_until(speed, player_is_grounded);
+    }
+}
+
+/// Condition for [`RunPhysicsUntilExt::run_physics_until`], handy for inspecting the end of a jump or fall.
+fn player_is_grounded(players: Query<&TnuaController, With<Player>>) -> bool {
+    players
+        .iter()
+        .any(|controller| controller.is_airborne().is_ok_and(|airborne| !airborne))
+}
+
+fn show_physics_diagnostics(world: &World, ui: &mut egui::Ui) {
+    let Some(diagnostics) = world.get_resource::<DiagnosticsStore>() else {
+        return;
+    };
+    egui::Grid::new("physics_diagnostics").show(ui, |ui| {
+        for path in PHYSICS_DIAGNOSTICS.iter() {
+            let Some(diagnostic) = diagnostics.get(path) else {
+                continue;
+            };
+            ui.label(path.as_str());
+            match diagnostic.smoothed() {
+                Some(value) => ui.label(format!("{value:.2}{}", diagnostic.suffix)),
+                None => ui.label("-"),
+            };
+            ui.end_row();
+        }
+    });
+}
+
+fn show_replay_controls(world: &mut World, ui: &mut egui::Ui) {
+    let mut request = None;
+    if let Some(recorder) = world.get_resource::<ReplayRecorder>() {
+        ui.label(format!(
+            "Recording {}: {} frames",
+            recorder.name(),
+            recorder.recorded_frames()
+        ));
+        if ui.button("Stop recording").clicked() {
+            request = Some(ReplayRequest::StopRecording);
+        }
+    } else if let Some(playback) = world.get_resource::<ReplayPlayback>() {
+        let (played, total) = playback.progress();
+        ui.label(format!(
+            "Playing {}: {played}/{total} frames",
+            playback.name()
+        ));
+        if ui.button("Stop playback").clicked() {
+            request = Some(ReplayRequest::StopPlayback);
+        }
+    } else {
+        ui.horizontal(|ui| {
+            if ui.button("Record").clicked() {
+                request = Some(ReplayRequest::StartRecording {
+                    name: QUICK_REPLAY.to_string(),
+                });
+            }
+            if ui.button("Play latest").clicked() {
+                request = Some(ReplayRequest::Play {
+                    name: QUICK_REPLAY.to_string(),
+                });
+            }
+        });
     }
+    if let Some(request) = request {
+        world.send_event(request);
+    }
+}
+
+/// Lists how many colliders are in each layer and which layers each of them interacts with.
+fn show_collision_layers(world: &mut World, ui: &mut egui::Ui) {
+    let mut colliders = world.query::<&CollisionLayers>();
+    egui::Grid::new("collision_layers").show(ui, |ui| {
+        ui.label("Layer");
+        ui.label("Members");
+        ui.label("Interacts with");
+        ui.end_row();
+        for layer in CollisionLayer::ALL {
+            let members: Vec<_> = colliders
+                .iter(world)
+                .filter(|layers| layers.memberships().contains(layer))
+                .collect();
+            let filters = members
+                .iter()
+                .flat_map(|layers| layers.filters().iter())
+                .collect::<LayerMask>();
+            ui.label(format!("{layer:?}"));
+            ui.label(members.len().to_string());
+            ui.label(format!("{:?}", filters.iter().collect::<Vec<_>>()));
+            ui.end_row();
+        }
+    });
+}
+
+/// Lists the meshes that didn't get a collider. They are also outlined in red in the level.
+fn show_collider_errors(world: &mut World, ui: &mut egui::Ui) {
+    let mut errors = world.query::<(Entity, &ColliderError, Option<&Name>)>();
+    if errors.iter(world).next().is_none() {
+        ui.label("None");
+        return;
+    }
+    egui::Grid::new("collider_errors").show(ui, |ui| {
+        for (entity, error, name) in errors.iter(world) {
+            let name = name.map(|name| name.as_str()).unwrap_or("unnamed");
+            ui.label(format!("{name} ({entity:?})"));
+            ui.colored_label(egui::Color32::RED, &error.reason);
+            ui.end_row();
+        }
+    });
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Resource, Reflect, Serialize, Deserialize)]
@@ -49,6 +221,7 @@ pub(crate) struct DevEditorState {
     pub(crate) open: bool,
     pub(crate) collider_render_enabled: bool,
     pub(crate) navmesh_render_enabled: bool,
+    pub(crate) physics_trace_enabled: bool,
 }
 
 fn handle_debug_render(
@@ -69,6 +242,30 @@ fn handle_debug_render(
     Ok(())
 }
 
+fn handle_navmesh_render(
+    state: Res<Editor>,
+    mut draw_nav_mesh: ResMut<DrawNavMesh>,
+) -> anyhow::Result<()> {
+    draw_nav_mesh.0 = state
+        .window_state::<DevEditorWindow>()
+        .context("Failed to read dev window state")?
+        .navmesh_render_enabled;
+    Ok(())
+}
+
+fn highlight_collider_errors(
+    mut gizmos: Gizmos,
+    errors: Query<(&GlobalTransform, Option<&Aabb>), With<ColliderError>>,
+) {
+    for (transform, aabb) in errors.iter() {
+        let (center, half_extents) = aabb
+            .map(|aabb| (Vec3::from(aabb.center), Vec3::from(aabb.half_extents)))
+            .unwrap_or((Vec3::ZERO, Vec3::splat(0.5)));
+        let local = Transform::from_translation(center).with_scale(half_extents * 2.);
+        gizmos.cuboid((*transform * local).compute_transform(), Color::RED);
+    }
+}
+
 fn set_cursor_grab_mode(
     mut events: EventReader<EditorEvent>,
     mut force_cursor_grab: ResMut<ForceCursorGrabMode>,
@@ -83,3 +280,21 @@ fn set_cursor_grab_mode(
         }
     }
 }
+
+fn handle_physics_time_actions(
+    actions: Query<&ActionState<UiAction>>,
+    mut physics_time: ResMut<PhysicsTime>,
+) {
+    for actions in actions.iter() {
+        if actions.just_pressed(&UiAction::TogglePhysicsPause) {
+            if physics_time.is_paused() {
+                physics_time.resume();
+            } else {
+                physics_time.pause();
+            }
+        }
+        if actions.just_pressed(&UiAction::StepPhysics) {
+            physics_time.step(1);
+        }
+    }
+}
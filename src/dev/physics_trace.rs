@@ -0,0 +1,78 @@
+use crate::dev::dev_editor::DevEditorWindow;
+use crate::physics_time::PHYSICS_DIAGNOSTICS;
+use crate::util::error;
+use anyhow::Context;
+use bevy::{diagnostic::DiagnosticsStore, prelude::*};
+use bevy_editor_pls::editor::Editor;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+/// File, relative to the working directory, the physics trace is written to.
+pub(crate) const PHYSICS_TRACE_PATH: &str = "physics_trace.csv";
+
+/// Writes the latest physics diagnostics to [`PHYSICS_TRACE_PATH`] every frame
+/// while the trace is enabled in the Foxtrot Dev editor window.
+/// Enabling the trace again overwrites the previous one.
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Last,
+        write_physics_trace
+            .pipe(error)
+            .run_if(resource_exists::<Editor>),
+    );
+}
+
+fn write_physics_trace(
+    editor: Res<Editor>,
+    diagnostics: Res<DiagnosticsStore>,
+    time: Res<Time<Real>>,
+    mut trace: Local<Option<BufWriter<File>>>,
+) -> anyhow::Result<()> {
+    let enabled = editor
+        .window_state::<DevEditorWindow>()
+        .context("Failed to read dev window state")?
+        .physics_trace_enabled;
+    if !enabled {
+        if let Some(mut writer) = trace.take() {
+            writer.flush().context("Failed to flush physics trace")?;
+            info!("Stopped writing physics trace to {PHYSICS_TRACE_PATH}");
+        }
+        return Ok(());
+    }
+    let writer = match trace.as_mut() {
+        Some(writer) => writer,
+        None => {
+            let file = File::create(PHYSICS_TRACE_PATH)
+                .with_context(|| format!("Failed to create physics trace {PHYSICS_TRACE_PATH}"))?;
+            let mut writer = BufWriter::new(file);
+            let header: Vec<_> = PHYSICS_DIAGNOSTICS
+                .iter()
+                .map(|path| path.as_str())
+                .collect();
+            writeln!(writer, "elapsed_seconds,{}", header.join(","))
+                .context("Failed to write physics trace header")?;
+            info!("Writing physics trace to {PHYSICS_TRACE_PATH}");
+            trace.insert(writer)
+        }
+    };
+    let values: Vec<_> = PHYSICS_DIAGNOSTICS
+        .iter()
+        .map(|path| {
+            diagnostics
+                .get(path)
+                .and_then(|diagnostic| diagnostic.value())
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        })
+        .collect();
+    writeln!(
+        writer,
+        "{},{}",
+        time.elapsed_seconds_f64(),
+        values.join(",")
+    )
+    .context("Failed to write physics trace")?;
+    Ok(())
+}
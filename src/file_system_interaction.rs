@@ -3,11 +3,20 @@ use bevy::prelude::*;
 pub(crate) mod asset_loading;
 pub(crate) mod audio;
 pub(crate) mod config;
+pub(crate) mod replay;
+pub(crate) mod save;
 
 /// Handles loading and saving of levels and save states to disk.
 /// Split into the following sub-plugins:
 /// - [`asset_loading::plugin`] handles loading of assets.els.
 /// - [`audio::plugin`]: Handles audio initialization
+/// - [`save::plugin`]: Handles saving and loading of the game state
+/// - [`replay::plugin`]: Handles recording and playing back player input
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((asset_loading::plugin, audio::plugin));
+    app.add_plugins((
+        asset_loading::plugin,
+        audio::plugin,
+        save::plugin,
+        replay::plugin,
+    ));
 }
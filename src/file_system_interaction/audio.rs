@@ -1,11 +1,22 @@
-use crate::{file_system_interaction::asset_loading::AudioAssets, GameState};
+use crate::{
+    file_system_interaction::asset_loading::AudioAssets, level_instantiation::on_spawn::Player,
+    movement::character_controller::Swim, GameState,
+};
 use bevy::prelude::*;
 use bevy_kira_audio::prelude::{Audio, *};
+use std::time::Duration;
 
-/// Handles initialization of all sounds.
+/// How long it takes for the sound to change when the player dives in or surfaces
+const UNDERWATER_FADE: Duration = Duration::from_millis(300);
+
+/// Handles initialization of all sounds and muffles them while the player is underwater.
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins(AudioPlugin)
-        .add_systems(OnExit(GameState::Loading), init_audio);
+        .add_systems(OnExit(GameState::Loading), init_audio)
+        .add_systems(
+            Update,
+            muffle_audio_underwater.run_if(in_state(GameState::Playing)),
+        );
 }
 
 #[derive(Debug, Clone, Resource)]
@@ -22,3 +33,22 @@ fn init_audio(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Res
         .handle();
     commands.insert_resource(AudioHandles { walking: handle });
 }
+
+/// bevy_kira_audio can't put a low-pass filter on a channel,
+/// so being underwater is approximated by making everything quieter and lower pitched.
+fn muffle_audio_underwater(
+    player: Query<&Swim, With<Player>>,
+    audio: Res<Audio>,
+    mut muffled: Local<bool>,
+) {
+    let submerged = player.iter().any(|swim| swim.submerged);
+    if submerged == *muffled {
+        return;
+    }
+    *muffled = submerged;
+    let (volume, playback_rate) = if submerged { (0.4, 0.8) } else { (1.0, 1.0) };
+    audio.set_volume(volume).linear_fade_in(UNDERWATER_FADE);
+    audio
+        .set_playback_rate(playback_rate)
+        .linear_fade_in(UNDERWATER_FADE);
+}
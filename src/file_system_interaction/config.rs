@@ -1,3 +1,4 @@
+use crate::physics_time::OverstepPolicy;
 use bevy::prelude::*;
 
 use serde::{Deserialize, Serialize};
@@ -7,6 +8,8 @@ use serde::{Deserialize, Serialize};
 pub(crate) struct GameConfig {
     pub(crate) camera: Camera,
     pub(crate) player: PlayerEffects,
+    pub(crate) physics: Physics,
+    pub(crate) movement: Movement,
 }
 
 #[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize, Default)]
@@ -63,3 +66,60 @@ pub(crate) struct ThirdPerson {
 pub(crate) struct PlayerEffects {
     pub(crate) sprint_effect_speed_threshold: f32,
 }
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize)]
+pub(crate) struct Physics {
+    /// Physics ticks per second
+    pub(crate) tick_rate: f64,
+    /// Maximum number of ticks run in a single frame
+    pub(crate) max_substeps: u32,
+    /// Maximum wall time in milliseconds spent on ticks in a single frame
+    pub(crate) max_catch_up_ms: f64,
+    /// What to do with ticks that were due, but could not be run this frame
+    pub(crate) overstep_policy: OverstepPolicy,
+    /// Write colliders built from level meshes to disk, so that the next start doesn't have to rebuild them
+    #[serde(default)]
+    pub(crate) persist_collider_cache: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize)]
+pub(crate) struct Movement {
+    pub(crate) player: MovementTuning,
+    pub(crate) npc: MovementTuning,
+}
+
+/// Applied to the character controller components of every character of an archetype, e.g. the [`Walk`](crate::movement::character_controller::Walk).
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize)]
+pub(crate) struct MovementTuning {
+    /// Top speed on the ground in m/s
+    pub(crate) speed: f32,
+    pub(crate) sprint_multiplier: f32,
+    /// Acceleration on the ground in m/s²
+    pub(crate) acceleration: f32,
+    /// Acceleration in the air in m/s². Lower values mean less air control.
+    pub(crate) air_acceleration: f32,
+    /// Turning speed in rad/s
+    pub(crate) turning_speed: f32,
+    /// How far below the float height the character still snaps to the ground
+    pub(crate) cling_distance: f32,
+    /// Steepest walkable slope in degrees
+    pub(crate) max_slope_degrees: f32,
+    /// Speed multiplier when walking backwards in first person
+    pub(crate) backwards_modifier: f32,
+    pub(crate) jump: JumpTuning,
+}
+
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize)]
+pub(crate) struct JumpTuning {
+    pub(crate) height: f32,
+    pub(crate) coyote_time: f32,
+    pub(crate) buffer_time: f32,
+    pub(crate) max_air_jumps: u32,
+    pub(crate) takeoff_extra_gravity: f32,
+    pub(crate) release_extra_gravity: f32,
+    pub(crate) fall_extra_gravity: f32,
+}
@@ -0,0 +1,613 @@
+use crate::{
+    file_system_interaction::save::{is_valid_slot_name, PendingLoad, SaveModel, SaveSnapshot},
+    level_instantiation::on_spawn::Player,
+    movement::platform::PlatformState,
+    physics_time::{run_physics_schedule, PhysicsSchedule, PhysicsTime, PhysicsTimeExt},
+    player_control::{
+        actions::{CameraAction, PlayerAction, UiAction},
+        camera::IngameCamera,
+    },
+    util::error,
+    GameState, GameSystemSet,
+};
+use anyhow::{bail, Context};
+use bevy::{prelude::*, time::TimeUpdateStrategy, utils::HashSet};
+use bevy_rapier3d::prelude::*;
+use leafwing_input_manager::{plugin::InputManagerSystem, prelude::ActionState};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, mem,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Directory, relative to the working directory, in which replays are stored.
+pub(crate) const REPLAY_DIR: &str = "replays";
+/// Replay written by the [`UiAction::ToggleReplayRecording`] action.
+pub(crate) const QUICK_REPLAY: &str = "latest";
+/// Bump this whenever [`Replay`] changes.
+pub(crate) const REPLAY_VERSION: u32 = 2;
+const REPLAY_EXTENSION: &str = "replay.ron";
+
+/// Records the player's input for every frame, together with the game state at the start of the recording,
+/// and plays it back deterministically. Recording and playback are controlled by sending [`ReplayRequest`]s.
+///
+/// Tnua and the embodiment systems run in [`Update`] on the frame's delta, while the simulation advances in ticks of the [`PhysicsSchedule`].
+/// Each recorded frame therefore stores its delta, the number of ticks that ran at its start and the input seen by the
+/// [`GameSystemSet::PlayerEmbodiment`] systems. During playback, the recorded delta is fed to [`Time`] through the [`TimeUpdateStrategy`],
+/// exactly the recorded number of ticks is run and the recorded input is written into the [`ActionState`]s right after the physics ran.
+/// Walking is relative to the camera, so the camera's transform is part of the recorded input.
+///
+/// The initial state is captured at the start of a frame, right after its ticks ran. Next to a [`SaveModel`], it contains the
+/// pose and velocity of every moving rigid body, e.g. NPCs, dynamic props and platforms, as well as the progress of platforms.
+/// Bodies are identified by their [`Name`], which Blender keeps unique. State outside of components, like Tnua's internals, is not captured.
+pub(super) fn plugin(app: &mut App) {
+    app.add_event::<ReplayRequest>()
+        .init_resource::<ReplayDirectory>()
+        .add_systems(
+            Update,
+            (
+                // Before the requests, so that the frame in which the recording stops is part of it
+                record_replay_frame
+                    .run_if(resource_exists::<ReplayRecorder>)
+                    .run_if(in_state(GameState::Playing))
+                    .before(GameSystemSet::PlayerEmbodiment),
+                handle_replay_actions,
+                handle_replay_requests.pipe(error),
+            )
+                .chain(),
+        )
+        .add_systems(
+            PreUpdate,
+            (
+                start_replay_recording
+                    .pipe(error)
+                    .run_if(resource_exists::<ReplayRecorder>),
+                apply_replay_frame.run_if(resource_exists::<ReplayPlayback>),
+            )
+                .after(run_physics_schedule)
+                .after(InputManagerSystem::Update),
+        )
+        .add_systems(
+            PhysicsSchedule,
+            count_replay_tick.run_if(resource_exists::<ReplayRecorder>),
+        );
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Event)]
+pub(crate) enum ReplayRequest {
+    /// Starts recording into the replay with the given name, overwriting it when stopped.
+    StartRecording { name: String },
+    /// Stops the recording and writes it to disk.
+    StopRecording,
+    /// Loads the initial state of the replay with the given name and plays back its input.
+    Play { name: String },
+    /// Stops the playback and hands control back to the player.
+    StopPlayback,
+}
+
+/// Where replays are stored.
+#[derive(Debug, Clone, PartialEq, Eq, Resource)]
+pub(crate) struct ReplayDirectory(pub(crate) PathBuf);
+
+impl Default for ReplayDirectory {
+    fn default() -> Self {
+        Self(PathBuf::from(REPLAY_DIR))
+    }
+}
+
+/// Everything that is written to a replay file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Replay {
+    pub(crate) version: u32,
+    /// Duration of a physics tick when the replay was recorded.
+    /// Playing back with a different tick rate diverges from the recording.
+    pub(crate) timestep: Duration,
+    pub(crate) initial_state: SaveModel,
+    /// State of the moving rigid bodies at the start of the recording, by [`Name`]
+    pub(crate) bodies: BTreeMap<String, BodyState>,
+    pub(crate) frames: Vec<ReplayFrame>,
+}
+
+/// Simulation state of a single rigid body.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct BodyState {
+    pub(crate) transform: Transform,
+    pub(crate) linvel: Vec3,
+    pub(crate) angvel: Vec3,
+    pub(crate) platform: Option<PlatformState>,
+}
+
+/// Everything that drove a single frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ReplayFrame {
+    /// Delta of [`Time<Virtual>`]
+    pub(crate) delta: Duration,
+    /// Physics ticks run at the start of the frame
+    pub(crate) ticks: u32,
+    pub(crate) player_actions: ActionState<PlayerAction>,
+    pub(crate) camera_actions: ActionState<CameraAction>,
+    pub(crate) camera_transform: Option<Transform>,
+}
+
+impl Replay {
+    pub(crate) fn to_ron(&self) -> anyhow::Result<String> {
+        ron::ser::to_string(self).context("Failed to serialize replay")
+    }
+
+    pub(crate) fn from_ron(serialized: &str) -> anyhow::Result<Self> {
+        let replay: Self = ron::from_str(serialized).context("Failed to deserialize replay")?;
+        if replay.version != REPLAY_VERSION {
+            bail!(
+                "Replay has version {}, but only version {REPLAY_VERSION} is supported",
+                replay.version
+            );
+        }
+        Ok(replay)
+    }
+}
+
+/// Present while a replay is being recorded.
+#[derive(Debug, Clone, Resource)]
+pub(crate) struct ReplayRecorder {
+    name: String,
+    /// `None` until the initial state is captured at the start of the frame after the recording was requested
+    replay: Option<Replay>,
+    /// Physics ticks run since the last recorded frame
+    ticks: u32,
+}
+
+impl ReplayRecorder {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn recorded_frames(&self) -> usize {
+        self.replay.as_ref().map_or(0, |replay| replay.frames.len())
+    }
+}
+
+/// Present while a replay is being played back.
+#[derive(Debug, Clone, Resource)]
+pub(crate) struct ReplayPlayback {
+    name: String,
+    replay: Replay,
+    /// Whether the bodies were restored to the initial state
+    restored: bool,
+    next_frame: usize,
+}
+
+impl ReplayPlayback {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of frames played back so far and the total number of frames.
+    pub(crate) fn progress(&self) -> (usize, usize) {
+        (self.next_frame, self.replay.frames.len())
+    }
+}
+
+/// The [`TimeUpdateStrategy`] that was in use before the playback replaced it with the recorded deltas.
+#[derive(Resource)]
+struct SuspendedTimeUpdateStrategy(TimeUpdateStrategy);
+
+pub(crate) fn replay_path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.{REPLAY_EXTENSION}"))
+}
+
+pub(crate) fn read_replay(dir: &Path, name: &str) -> anyhow::Result<Replay> {
+    let path = replay_path(dir, name);
+    let serialized = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read replay {}", path.display()))?;
+    Replay::from_ron(&serialized)
+        .with_context(|| format!("Failed to load replay {}", path.display()))
+}
+
+pub(crate) fn write_replay(dir: &Path, name: &str, replay: &Replay) -> anyhow::Result<()> {
+    if !is_valid_slot_name(name) {
+        bail!("Invalid replay name {name:?}");
+    }
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create replay directory {}", dir.display()))?;
+    let path = replay_path(dir, name);
+    fs::write(&path, replay.to_ron()?)
+        .with_context(|| format!("Failed to write replay {}", path.display()))
+}
+
+fn handle_replay_actions(
+    actions: Query<&ActionState<UiAction>>,
+    recorder: Option<Res<ReplayRecorder>>,
+    mut replay_requests: EventWriter<ReplayRequest>,
+) {
+    for actions in actions.iter() {
+        if actions.just_pressed(&UiAction::ToggleReplayRecording) {
+            replay_requests.send(if recorder.is_some() {
+                ReplayRequest::StopRecording
+            } else {
+                ReplayRequest::StartRecording {
+                    name: QUICK_REPLAY.to_string(),
+                }
+            });
+        }
+    }
+}
+
+fn handle_replay_requests(
+    mut commands: Commands,
+    mut replay_requests: EventReader<ReplayRequest>,
+    replay_directory: Res<ReplayDirectory>,
+    recorder: Option<Res<ReplayRecorder>>,
+    playback: Option<Res<ReplayPlayback>>,
+    mut physics_time: ResMut<PhysicsTime>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+) -> anyhow::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("handle_replay_requests").entered();
+    for request in replay_requests.read() {
+        match request {
+            ReplayRequest::StartRecording { name } => {
+                if playback.is_some() {
+                    bail!("Cannot record a replay while another one is being played back");
+                }
+                if !is_valid_slot_name(name) {
+                    bail!("Invalid replay name {name:?}");
+                }
+                commands.insert_resource(ReplayRecorder {
+                    name: name.clone(),
+                    replay: None,
+                    ticks: 0,
+                });
+                info!("Started recording replay {name}");
+            }
+            ReplayRequest::StopRecording => {
+                let Some(recorder) = recorder.as_ref() else {
+                    continue;
+                };
+                commands.remove_resource::<ReplayRecorder>();
+                let Some(replay) = recorder.replay.as_ref() else {
+                    info!("Discarded replay {} before it started", recorder.name);
+                    continue;
+                };
+                write_replay(&replay_directory.0, &recorder.name, replay)?;
+                info!(
+                    "Saved replay {} with {} frames",
+                    recorder.name,
+                    recorder.recorded_frames()
+                );
+            }
+            ReplayRequest::Play { name } => {
+                if recorder.is_some() {
+                    bail!("Cannot play back a replay while recording one");
+                }
+                let replay = read_replay(&replay_directory.0, name)?;
+                if replay.timestep != physics_time.context().timestep {
+                    warn!(
+                        "Replay {name} was recorded with a timestep of {:?}, but the current one is {:?}. The playback will diverge.",
+                        replay.timestep,
+                        physics_time.context().timestep
+                    );
+                }
+                // The frame in which the bodies are restored already needs the delta of the first frame
+                let first_delta = replay
+                    .frames
+                    .first()
+                    .map_or(replay.timestep, |frame| frame.delta);
+                let previous_strategy = mem::replace(
+                    &mut *time_update_strategy,
+                    TimeUpdateStrategy::ManualDuration(first_delta),
+                );
+                if playback.is_none() {
+                    commands.insert_resource(SuspendedTimeUpdateStrategy(previous_strategy));
+                }
+                commands.insert_resource(PendingLoad(replay.initial_state.clone()));
+                commands.insert_resource(ReplayPlayback {
+                    name: name.clone(),
+                    replay,
+                    restored: false,
+                    next_frame: 0,
+                });
+                // Don't tick until the initial state is restored
+                physics_time.pause();
+                info!("Started playing back replay {name}");
+            }
+            ReplayRequest::StopPlayback => {
+                if playback.is_some() {
+                    stop_playback(&mut commands, &mut physics_time);
+                    info!("Stopped replay playback");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn stop_playback(commands: &mut Commands, physics_time: &mut PhysicsTime) {
+    commands.remove_resource::<ReplayPlayback>();
+    commands.add(|world: &mut World| {
+        if let Some(SuspendedTimeUpdateStrategy(strategy)) = world.remove_resource() {
+            world.insert_resource(strategy);
+        }
+    });
+    physics_time.resume();
+}
+
+fn start_replay_recording(
+    mut commands: Commands,
+    mut recorder: ResMut<ReplayRecorder>,
+    physics_time: Res<PhysicsTime>,
+    snapshot: SaveSnapshot,
+    bodies: Query<(
+        &Name,
+        &RigidBody,
+        &Transform,
+        Option<&Velocity>,
+        Option<&PlatformState>,
+    )>,
+) -> anyhow::Result<()> {
+    if recorder.replay.is_some() {
+        return Ok(());
+    }
+    let initial_state = snapshot.capture();
+    if initial_state.is_err() {
+        commands.remove_resource::<ReplayRecorder>();
+    }
+    recorder.replay = Some(Replay {
+        version: REPLAY_VERSION,
+        timestep: physics_time.context().timestep,
+        initial_state: initial_state.context("Failed to start recording a replay")?,
+        bodies: capture_bodies(&bodies),
+        frames: Vec::new(),
+    });
+    // The ticks of this frame ran before the initial state
+    recorder.ticks = 0;
+    Ok(())
+}
+
+fn capture_bodies(
+    bodies: &Query<(
+        &Name,
+        &RigidBody,
+        &Transform,
+        Option<&Velocity>,
+        Option<&PlatformState>,
+    )>,
+) -> BTreeMap<String, BodyState> {
+    let mut states = BTreeMap::new();
+    let mut duplicates = HashSet::new();
+    for (name, rigid_body, transform, velocity, platform) in bodies.iter() {
+        if matches!(rigid_body, RigidBody::Fixed) {
+            continue;
+        }
+        let state = BodyState {
+            transform: *transform,
+            linvel: velocity.map_or(Vec3::ZERO, |velocity| velocity.linvel),
+            angvel: velocity.map_or(Vec3::ZERO, |velocity| velocity.angvel),
+            platform: platform.cloned(),
+        };
+        if states.insert(name.to_string(), state).is_some() {
+            duplicates.insert(name.to_string());
+        }
+    }
+    for name in duplicates {
+        warn!("Several rigid bodies are named {name}, so their state is not part of the replay");
+        states.remove(&name);
+    }
+    states
+}
+
+fn count_replay_tick(mut recorder: ResMut<ReplayRecorder>) {
+    recorder.ticks += 1;
+}
+
+fn record_replay_frame(
+    time: Res<Time>,
+    mut recorder: ResMut<ReplayRecorder>,
+    player_query: Query<&ActionState<PlayerAction>, With<Player>>,
+    camera_query: Query<(&ActionState<CameraAction>, &Transform), With<IngameCamera>>,
+) {
+    let recorder = recorder.as_mut();
+    let Some(replay) = recorder.replay.as_mut() else {
+        return;
+    };
+    let Ok(player_actions) = player_query.get_single() else {
+        return;
+    };
+    let camera = camera_query.get_single().ok();
+    replay.frames.push(ReplayFrame {
+        delta: time.delta(),
+        ticks: mem::take(&mut recorder.ticks),
+        player_actions: player_actions.clone(),
+        camera_actions: camera
+            .map(|(actions, _)| actions.clone())
+            .unwrap_or_default(),
+        camera_transform: camera.map(|(_, transform)| *transform),
+    });
+}
+
+fn apply_replay_frame(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    pending_load: Option<Res<PendingLoad>>,
+    mut physics_time: ResMut<PhysicsTime>,
+    mut time_update_strategy: ResMut<TimeUpdateStrategy>,
+    mut bodies: Query<
+        (
+            &Name,
+            &mut Transform,
+            Option<&mut Velocity>,
+            Option<&mut PlatformState>,
+        ),
+        (With<RigidBody>, Without<IngameCamera>),
+    >,
+    mut player_query: Query<&mut ActionState<PlayerAction>, With<Player>>,
+    mut camera_query: Query<(&mut ActionState<CameraAction>, &mut Transform), With<IngameCamera>>,
+) {
+    if pending_load.is_some() {
+        return;
+    }
+    let playback = playback.as_mut();
+    if !playback.restored {
+        for (name, mut transform, velocity, platform) in bodies.iter_mut() {
+            let Some(state) = playback.replay.bodies.get(name.as_str()) else {
+                continue;
+            };
+            *transform = state.transform;
+            if let Some(mut velocity) = velocity {
+                velocity.linvel = state.linvel;
+                velocity.angvel = state.angvel;
+            }
+            if let (Some(mut platform), Some(platform_state)) = (platform, state.platform.as_ref())
+            {
+                *platform = platform_state.clone();
+            }
+        }
+        playback.restored = true;
+    }
+    let Some(frame) = playback.replay.frames.get(playback.next_frame) else {
+        stop_playback(&mut commands, &mut physics_time);
+        info!("Finished playing back replay {}", playback.name);
+        return;
+    };
+    for mut player_actions in player_query.iter_mut() {
+        *player_actions = frame.player_actions.clone();
+    }
+    for (mut camera_actions, mut camera_transform) in camera_query.iter_mut() {
+        *camera_actions = frame.camera_actions.clone();
+        if let Some(transform) = frame.camera_transform {
+            *camera_transform = transform;
+        }
+    }
+    playback.next_frame += 1;
+
+    // Set up the next frame, whose delta is determined and whose ticks are run before this system runs again
+    if let Some(next_frame) = playback.replay.frames.get(playback.next_frame) {
+        *time_update_strategy = TimeUpdateStrategy::ManualDuration(next_frame.delta);
+        if next_frame.ticks > 0 {
+            physics_time.step(next_frame.ticks);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_system_interaction::save;
+    use crate::physics_time::DEFAULT_TIMESTEP;
+    use crate::test_harness::{TempDir, TestApp, TestEntity};
+
+    /// Upper bound for frames spent loading and playing back a replay
+    const MAX_PLAYBACK_UPDATES: u32 = 1_000;
+
+    fn send(app: &mut TestApp, request: ReplayRequest) {
+        app.app.world.send_event(request);
+        // Physics is paused, so this doesn't tick
+        app.app.update();
+    }
+
+    /// Runs frames at a varying frame rate: every third frame is half as long and runs no physics tick.
+    fn run_frames(app: &mut TestApp, frames: u32) {
+        for frame in 0..frames {
+            if frame % 3 == 0 {
+                app.app
+                    .insert_resource(TimeUpdateStrategy::ManualDuration(DEFAULT_TIMESTEP / 2));
+                app.app.update();
+                app.app
+                    .insert_resource(TimeUpdateStrategy::ManualDuration(DEFAULT_TIMESTEP));
+            } else {
+                app.tick();
+            }
+        }
+    }
+
+    #[test]
+    fn playback_ends_where_recording_did() {
+        let dir = TempDir::new("replay");
+        let mut app = TestApp::new();
+        app.app
+            .add_plugins((save::plugin, plugin))
+            .insert_resource(ReplayDirectory(dir.0.clone()));
+        app.wait_for_dialogue_runner();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        let prop = app.spawn(TestEntity::prop(0.5).at(Vec3::new(3., 0.5, 0.)));
+        app.ticks(120);
+        let start = app.translation(player);
+
+        send(
+            &mut app,
+            ReplayRequest::StartRecording {
+                name: "test".to_string(),
+            },
+        );
+        app.press(KeyCode::KeyW);
+        run_frames(&mut app, 40);
+        app.press(KeyCode::Space);
+        run_frames(&mut app, 20);
+        app.release(KeyCode::Space);
+        app.release(KeyCode::KeyW);
+        run_frames(&mut app, 40);
+        let recorded_end = *app.app.world.get::<Transform>(player).unwrap();
+        let recorded_prop_end = app.translation(prop);
+        send(&mut app, ReplayRequest::StopRecording);
+
+        let replay = read_replay(&dir.0, "test").unwrap();
+        // The frame that stopped the recording is part of it
+        assert_eq!(replay.frames.len(), 101);
+        assert_eq!(
+            replay.frames.iter().map(|frame| frame.ticks).sum::<u32>(),
+            65
+        );
+        assert!(replay.bodies.contains_key("Prop"));
+        assert!(replay.bodies.contains_key("Player"));
+        assert!(
+            start.distance(recorded_end.translation) > 1.,
+            "Player should have moved away from {start}, but is at {}",
+            recorded_end.translation
+        );
+
+        // The playback has to put the prop back, too
+        app.app
+            .world
+            .get_mut::<Transform>(prop)
+            .unwrap()
+            .translation = Vec3::new(3., 4., 3.);
+        app.app.world.get_mut::<Velocity>(prop).unwrap().linvel = Vec3::new(0., 0., -2.);
+
+        send(
+            &mut app,
+            ReplayRequest::Play {
+                name: "test".to_string(),
+            },
+        );
+        for _ in 0..MAX_PLAYBACK_UPDATES {
+            if !app.app.world.contains_resource::<ReplayPlayback>() {
+                break;
+            }
+            app.app.update();
+        }
+        assert!(
+            !app.app.world.contains_resource::<ReplayPlayback>(),
+            "Playback did not finish after {MAX_PLAYBACK_UPDATES} updates"
+        );
+
+        let played_end = *app.app.world.get::<Transform>(player).unwrap();
+        assert!(
+            played_end.translation.distance(recorded_end.translation) < 0.05,
+            "Playback ended at {}, but the recording at {}",
+            played_end.translation,
+            recorded_end.translation
+        );
+        assert!(played_end.rotation.angle_between(recorded_end.rotation) < 0.05);
+        assert!(
+            app.translation(prop).distance(recorded_prop_end) < 0.05,
+            "Prop ended at {}, but at {recorded_prop_end} in the recording",
+            app.translation(prop)
+        );
+        assert!(matches!(
+            app.app.world.resource::<TimeUpdateStrategy>(),
+            TimeUpdateStrategy::ManualDuration(delta) if *delta == DEFAULT_TIMESTEP
+        ));
+    }
+}
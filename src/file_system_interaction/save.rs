@@ -0,0 +1,519 @@
+use crate::{
+    level_instantiation::{map::LEVEL_NAME, on_spawn::Player},
+    movement::character_controller::{Jump, Sprinting, Stamina, Walk},
+    player_control::{
+        actions::{ActionsFrozen, UiAction},
+        camera::IngameCamera,
+    },
+    util::error,
+    world_interaction::dialog::{CurrentDialogTarget, YarnNode},
+    GameState,
+};
+use anyhow::{bail, Context};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier3d::prelude::Velocity;
+use bevy_tnua::controller::TnuaController;
+use bevy_yarnspinner::prelude::{DialogueRunner, YarnValue};
+use leafwing_input_manager::prelude::ActionState;
+use serde::{Deserialize, Serialize};
+pub(crate) use slot::*;
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+mod slot;
+
+/// Directory, relative to the working directory, in which save files are stored.
+pub(crate) const SAVE_DIR: &str = "saves";
+/// Slot used by the quick-save and quick-load actions.
+pub(crate) const QUICK_SAVE_SLOT: &str = "quicksave";
+/// Bump this whenever [`SaveModel`] changes. Older versions are still loaded,
+/// so new fields need a `#[serde(default)]`.
+pub(crate) const SAVE_VERSION: u32 = 3;
+
+/// Handles serializing the game state into a save file and restoring it again.
+/// Saving and loading is triggered by sending a [`GameSaveRequest`] or [`GameLoadRequest`],
+/// or by the quick-save and quick-load [`UiAction`]s.
+/// Every save file lives in its own slot inside the [`SaveDirectory`], see [`SaveSlots`].
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<PlayTime>()
+        .register_type::<SaveMetadata>()
+        .add_event::<GameSaveRequest>()
+        .add_event::<GameLoadRequest>()
+        .init_resource::<SaveDirectory>()
+        .init_resource::<SaveSlots>()
+        .init_resource::<PlayTime>()
+        .add_systems(Startup, refresh_save_slots)
+        .add_systems(OnEnter(GameState::Menu), refresh_save_slots)
+        .add_systems(
+            Update,
+            (
+                track_play_time.run_if(in_state(GameState::Playing)),
+                handle_quick_save_actions,
+                handle_save_requests.pipe(error),
+                handle_load_requests.pipe(error),
+                apply_pending_load
+                    .pipe(error)
+                    .run_if(resource_exists::<PendingLoad>),
+            )
+                .chain(),
+        );
+}
+
+/// Requests the current game state to be written to the given save slot.
+#[derive(Debug, Clone, Eq, PartialEq, Event)]
+pub(crate) struct GameSaveRequest {
+    pub(crate) slot: String,
+}
+
+/// Requests the game state stored in the given save slot to be restored.
+#[derive(Debug, Clone, Eq, PartialEq, Event)]
+pub(crate) struct GameLoadRequest {
+    pub(crate) slot: String,
+}
+
+/// A save file that was read from disk, but not yet applied because the level is not fully spawned.
+#[derive(Debug, Clone, PartialEq, Resource)]
+pub(crate) struct PendingLoad(pub(crate) SaveModel);
+
+/// Where the save slots are stored.
+#[derive(Debug, Clone, PartialEq, Eq, Resource)]
+pub(crate) struct SaveDirectory(pub(crate) PathBuf);
+
+impl Default for SaveDirectory {
+    fn default() -> Self {
+        Self(PathBuf::from(SAVE_DIR))
+    }
+}
+
+/// Time spent in [`GameState::Playing`] in the current game, excluding pauses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource, Reflect, Default)]
+#[reflect(Resource)]
+pub(crate) struct PlayTime(pub(crate) Duration);
+
+/// Everything that is written to a save file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SaveModel {
+    pub(crate) version: u32,
+    #[serde(default)]
+    pub(crate) metadata: SaveMetadata,
+    pub(crate) player: PlayerSave,
+    pub(crate) camera: Option<IngameCamera>,
+    /// The [`YarnNode`] of the entity the player was talking to.
+    /// Entities are not stable across loads, so we don't store the [`CurrentDialogTarget`] directly.
+    pub(crate) dialog_target: Option<String>,
+    pub(crate) yarn_variables: BTreeMap<String, SavedYarnValue>,
+}
+
+/// Loading only restores the runtime state of the character controller components, e.g. requests and the current stamina.
+/// Their tuning stays as configured, so that saves don't carry outdated tuning forward.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct PlayerSave {
+    pub(crate) transform: Transform,
+    pub(crate) walk: Walk,
+    pub(crate) jump: Jump,
+    pub(crate) sprinting: Sprinting,
+    #[serde(default)]
+    pub(crate) stamina: Stamina,
+}
+
+/// Mirror of [`YarnValue`] with a stable serialization format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) enum SavedYarnValue {
+    Number(f32),
+    String(String),
+    Boolean(bool),
+}
+
+impl From<YarnValue> for SavedYarnValue {
+    fn from(value: YarnValue) -> Self {
+        match value {
+            YarnValue::Number(number) => Self::Number(number),
+            YarnValue::String(string) => Self::String(string),
+            YarnValue::Boolean(boolean) => Self::Boolean(boolean),
+        }
+    }
+}
+
+impl From<SavedYarnValue> for YarnValue {
+    fn from(value: SavedYarnValue) -> Self {
+        match value {
+            SavedYarnValue::Number(number) => Self::Number(number),
+            SavedYarnValue::String(string) => Self::String(string),
+            SavedYarnValue::Boolean(boolean) => Self::Boolean(boolean),
+        }
+    }
+}
+
+impl SaveModel {
+    pub(crate) fn to_ron(&self) -> anyhow::Result<String> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .context("Failed to serialize save file")
+    }
+
+    /// Older versions are migrated by filling in the fields they lack with their defaults.
+    pub(crate) fn from_ron(serialized: &str) -> anyhow::Result<Self> {
+        let mut model: Self =
+            ron::from_str(serialized).context("Failed to deserialize save file")?;
+        if model.version > SAVE_VERSION {
+            bail!(
+                "Save file has version {}, but the newest supported version is {SAVE_VERSION}",
+                model.version
+            );
+        }
+        model.version = SAVE_VERSION;
+        Ok(model)
+    }
+}
+
+fn refresh_save_slots(mut save_slots: ResMut<SaveSlots>, save_directory: Res<SaveDirectory>) {
+    save_slots.refresh(&save_directory.0);
+}
+
+fn track_play_time(time: Res<Time<Virtual>>, mut play_time: ResMut<PlayTime>) {
+    play_time.0 += time.delta();
+}
+
+fn handle_quick_save_actions(
+    actions: Query<&ActionState<UiAction>>,
+    actions_frozen: Res<ActionsFrozen>,
+    mut save_requests: EventWriter<GameSaveRequest>,
+    mut load_requests: EventWriter<GameLoadRequest>,
+) {
+    // Don't save in the middle of a dialog or while the game is paused
+    if actions_frozen.is_frozen() {
+        return;
+    }
+    for actions in actions.iter() {
+        if actions.just_pressed(&UiAction::QuickSave) {
+            save_requests.send(GameSaveRequest {
+                slot: QUICK_SAVE_SLOT.to_string(),
+            });
+        } else if actions.just_pressed(&UiAction::QuickLoad) {
+            load_requests.send(GameLoadRequest {
+                slot: QUICK_SAVE_SLOT.to_string(),
+            });
+        }
+    }
+}
+
+/// Everything needed to capture the current game state as a [`SaveModel`].
+#[derive(SystemParam)]
+pub(crate) struct SaveSnapshot<'w, 's> {
+    play_time: Res<'w, PlayTime>,
+    player_query: Query<
+        'w,
+        's,
+        (
+            &'static Transform,
+            &'static Walk,
+            &'static Jump,
+            &'static Sprinting,
+            &'static Stamina,
+        ),
+        With<Player>,
+    >,
+    camera_query: Query<'w, 's, &'static IngameCamera>,
+    dialog_target: Res<'w, CurrentDialogTarget>,
+    yarn_nodes: Query<'w, 's, &'static YarnNode>,
+    dialogue_runner: Query<'w, 's, &'static DialogueRunner>,
+}
+
+impl SaveSnapshot<'_, '_> {
+    pub(crate) fn capture(&self) -> anyhow::Result<SaveModel> {
+        let (transform, walk, jump, sprinting, stamina) = self
+            .player_query
+            .get_single()
+            .context("Failed to get the player while saving")?;
+        let dialog_target = self
+            .dialog_target
+            .0
+            .and_then(|entity| self.yarn_nodes.get(entity).ok())
+            .map(|yarn_node| yarn_node.0.clone());
+        let yarn_variables = self
+            .dialogue_runner
+            .get_single()
+            .map(|runner| {
+                runner
+                    .variable_storage()
+                    .variables()
+                    .into_iter()
+                    .map(|(name, value)| (name, value.into()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let summary = match dialog_target.as_ref() {
+            Some(node) => format!("Talking to {node}"),
+            None => {
+                let position = transform.translation;
+                format!(
+                    "Exploring near ({:.0}, {:.0}, {:.0})",
+                    position.x, position.y, position.z
+                )
+            }
+        };
+        Ok(SaveModel {
+            version: SAVE_VERSION,
+            metadata: SaveMetadata::now(LEVEL_NAME, self.play_time.0, summary),
+            player: PlayerSave {
+                transform: *transform,
+                walk: walk.clone(),
+                jump: jump.clone(),
+                sprinting: sprinting.clone(),
+                stamina: stamina.clone(),
+            },
+            camera: self.camera_query.get_single().ok().cloned(),
+            dialog_target,
+            yarn_variables,
+        })
+    }
+}
+
+fn handle_save_requests(
+    mut save_requests: EventReader<GameSaveRequest>,
+    save_directory: Res<SaveDirectory>,
+    mut save_slots: ResMut<SaveSlots>,
+    snapshot: SaveSnapshot,
+) -> anyhow::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("handle_save_requests").entered();
+    for save in save_requests.read() {
+        let model = snapshot.capture()?;
+        write_slot(&save_directory.0, &save.slot, &model)?;
+        info!("Saved game to slot {}", save.slot);
+        save_slots.refresh(&save_directory.0);
+    }
+    Ok(())
+}
+
+/// Resets the state of the current game like starting a new one does, so nothing of it leaks into the loaded game.
+fn handle_load_requests(
+    mut commands: Commands,
+    mut load_requests: EventReader<GameLoadRequest>,
+    save_directory: Res<SaveDirectory>,
+    mut play_time: ResMut<PlayTime>,
+    mut dialog_target: ResMut<CurrentDialogTarget>,
+) -> anyhow::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("handle_load_requests").entered();
+    for load in load_requests.read() {
+        let model = read_slot(&save_directory.0, &load.slot)?;
+        *play_time = default();
+        dialog_target.0 = None;
+        commands.insert_resource(PendingLoad(model));
+    }
+    Ok(())
+}
+
+/// Waits until the player and the dialogue runner exist, then restores the pending save file.
+fn apply_pending_load(
+    mut commands: Commands,
+    pending_load: Res<PendingLoad>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Walk,
+            &mut Jump,
+            &mut Sprinting,
+            &mut Stamina,
+            Option<&mut Velocity>,
+        ),
+        (With<Player>, With<TnuaController>),
+    >,
+    mut camera_query: Query<&mut IngameCamera>,
+    mut dialogue_runner: Query<&mut DialogueRunner>,
+    yarn_nodes: Query<(Entity, &YarnNode)>,
+    mut dialog_target: ResMut<CurrentDialogTarget>,
+    mut actions_frozen: ResMut<ActionsFrozen>,
+    mut play_time: ResMut<PlayTime>,
+) -> anyhow::Result<()> {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("apply_pending_load").entered();
+    let Ok((mut transform, mut walk, mut jump, mut sprinting, mut stamina, velocity)) =
+        player_query.get_single_mut()
+    else {
+        return Ok(());
+    };
+    let Ok(mut dialogue_runner) = dialogue_runner.get_single_mut() else {
+        return Ok(());
+    };
+    let model = &pending_load.0;
+    commands.remove_resource::<PendingLoad>();
+
+    *transform = model.player.transform;
+    // The tuning comes from the player's spawn and the GameConfig, so only restore what changes while playing
+    walk.direction = model.player.walk.direction;
+    jump.requested = model.player.jump.requested;
+    sprinting.requested = model.player.sprinting.requested;
+    stamina.current = model.player.stamina.current.min(stamina.max);
+    stamina.regen_delay_remaining = model.player.stamina.regen_delay_remaining;
+    stamina.exhausted = model.player.stamina.exhausted;
+    play_time.0 = model.metadata.play_time;
+    if let Some(mut velocity) = velocity {
+        *velocity = Velocity::zero();
+    }
+    if let Some(camera) = model.camera.as_ref() {
+        for mut ingame_camera in camera_query.iter_mut() {
+            *ingame_camera = camera.clone();
+        }
+    }
+
+    let variable_storage = dialogue_runner.variable_storage_mut();
+    for (name, value) in model.yarn_variables.iter() {
+        variable_storage
+            .set(name.clone(), value.clone().into())
+            .map_err(|e| anyhow::Error::msg(format!("{e:?}")))
+            .with_context(|| format!("Failed to restore yarn variable {name}"))?;
+    }
+
+    if let Some(node) = model.dialog_target.as_ref() {
+        let (target, _) = yarn_nodes
+            .iter()
+            .find(|(_, yarn_node)| &yarn_node.0 == node)
+            .with_context(|| format!("Failed to find dialog target with yarn node {node}"))?;
+        if !dialogue_runner.is_running() {
+            // Restart the conversation the player was in when saving
+            dialogue_runner.start_node(node);
+            actions_frozen.freeze();
+        }
+        dialog_target.0.replace(target);
+    }
+    info!("Loaded save file");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_system_interaction::config::GameConfig;
+    use crate::test_harness::{TestApp, TestEntity};
+
+    pub(super) fn save_model() -> SaveModel {
+        SaveModel {
+            version: SAVE_VERSION,
+            metadata: SaveMetadata {
+                timestamp: 1_700_000_000,
+                level: LEVEL_NAME.to_string(),
+                play_time: Duration::from_secs(90),
+                summary: "Talking to Follower".to_string(),
+            },
+            player: PlayerSave {
+                transform: Transform::from_xyz(1., 2., 3.).with_rotation(Quat::from_rotation_y(1.)),
+                walk: Walk::default(),
+                jump: Jump::default(),
+                sprinting: Sprinting::default(),
+                stamina: Stamina {
+                    current: 42.,
+                    ..default()
+                },
+            },
+            camera: Some(IngameCamera::default()),
+            dialog_target: Some("Follower".to_string()),
+            yarn_variables: BTreeMap::from([
+                ("$met_follower".to_string(), SavedYarnValue::Boolean(true)),
+                ("$coins".to_string(), SavedYarnValue::Number(3.)),
+                (
+                    "$name".to_string(),
+                    SavedYarnValue::String("Fox".to_string()),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn save_model_round_trips_through_ron() {
+        let model = save_model();
+
+        let loaded = SaveModel::from_ron(&model.to_ron().unwrap()).unwrap();
+
+        assert_eq!(loaded, model);
+    }
+
+    #[test]
+    fn version_1_save_is_migrated() {
+        // Written by version 1, which had neither metadata nor stamina and fewer character controller fields
+        let serialized = r#"(
+            version: 1,
+            player: (
+                transform: (
+                    translation: (1.0, 2.0, 3.0),
+                    rotation: (0.0, 0.0, 0.0, 1.0),
+                    scale: (1.0, 1.0, 1.0),
+                ),
+                walk: (speed: 8.0, direction: None),
+                jump: (height: 1.0, requested: false),
+                sprinting: (multiplier: 1.5, requested: false),
+            ),
+            camera: Some((
+                target: (1.0, 2.0, 3.0),
+                secondary_target: None,
+                desired_distance: 5.0,
+                kind: ThirdPerson,
+            )),
+            dialog_target: Some("Follower"),
+            yarn_variables: {"$met_follower": Boolean(true)},
+        )"#;
+
+        let loaded = SaveModel::from_ron(serialized).unwrap();
+
+        assert_eq!(loaded.version, SAVE_VERSION);
+        assert_eq!(loaded.player.transform, Transform::from_xyz(1., 2., 3.));
+        assert_eq!(loaded.player.walk.speed, 8.);
+        assert_eq!(
+            loaded.player.walk.acceleration,
+            Walk::default().acceleration
+        );
+        assert_eq!(loaded.player.jump.height, 1.);
+        assert_eq!(
+            loaded.player.jump.max_air_jumps,
+            Jump::default().max_air_jumps
+        );
+        assert_eq!(loaded.player.sprinting, Sprinting::default());
+        assert_eq!(loaded.player.stamina, Stamina::default());
+        assert_eq!(loaded.metadata, SaveMetadata::default());
+        assert_eq!(loaded.dialog_target.as_deref(), Some("Follower"));
+    }
+
+    #[test]
+    fn loading_keeps_movement_tuning_of_config() {
+        let mut app = TestApp::with_config();
+        app.app.add_plugins(plugin);
+        app.wait_for_dialogue_runner();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.tick();
+        app.config_mut().movement.player.speed = 3.;
+        app.config_mut().movement.player.jump.height = 0.5;
+        app.tick();
+
+        // Written before the config changed
+        let mut model = save_model();
+        model.version = 1;
+        model.player.walk.speed = 8.;
+        model.player.jump.height = 1.;
+        model.player.sprinting.multiplier = 10.;
+        model.dialog_target = None;
+        app.app.insert_resource(PendingLoad(model));
+        app.tick();
+
+        assert!(!app.app.world.contains_resource::<PendingLoad>());
+        let world = &app.app.world;
+        let tuning = &world.resource::<GameConfig>().movement.player;
+        assert_eq!(world.get::<Walk>(player).unwrap().speed, 3.);
+        assert_eq!(world.get::<Jump>(player).unwrap().height, 0.5);
+        assert_eq!(
+            world.get::<Sprinting>(player).unwrap().multiplier,
+            tuning.sprint_multiplier
+        );
+        // The runtime state still comes from the save
+        assert!(world.get::<Stamina>(player).unwrap().current < 50.);
+    }
+
+    #[test]
+    fn save_from_newer_version_is_rejected() {
+        let model = SaveModel {
+            version: SAVE_VERSION + 1,
+            ..save_model()
+        };
+
+        assert!(SaveModel::from_ron(&model.to_ron().unwrap()).is_err());
+    }
+}
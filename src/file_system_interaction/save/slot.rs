@@ -0,0 +1,250 @@
+use crate::file_system_interaction::save::SaveModel;
+use anyhow::Context;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const SLOT_EXTENSION: &str = "ron";
+
+/// Summary of a save file that can be shown without restoring it.
+#[derive(Debug, Clone, PartialEq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize)]
+pub(crate) struct SaveMetadata {
+    /// Seconds since the Unix epoch at which the save was written
+    pub(crate) timestamp: u64,
+    pub(crate) level: String,
+    pub(crate) play_time: Duration,
+    /// Short human-readable description of the game state
+    pub(crate) summary: String,
+}
+
+impl SaveMetadata {
+    pub(crate) fn now(level: impl Into<String>, play_time: Duration, summary: String) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            timestamp,
+            level: level.into(),
+            play_time,
+            summary,
+        }
+    }
+}
+
+/// A save file on disk, identified by its slot name.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SaveSlot {
+    pub(crate) name: String,
+    pub(crate) metadata: SaveMetadata,
+}
+
+/// All save slots found in the [`SaveDirectory`](crate::file_system_interaction::save::SaveDirectory),
+/// newest first. Call [`SaveSlots::refresh`] after changing the directory's contents.
+#[derive(Debug, Clone, PartialEq, Resource, Default)]
+pub(crate) struct SaveSlots(pub(crate) Vec<SaveSlot>);
+
+impl SaveSlots {
+    pub(crate) fn refresh(&mut self, dir: &Path) {
+        self.0 = list_slots(dir).unwrap_or_else(|e| {
+            error!("Failed to list save slots: {e:?}");
+            Vec::new()
+        });
+    }
+
+    pub(crate) fn newest(&self) -> Option<&SaveSlot> {
+        self.0.first()
+    }
+
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.0.iter().any(|slot| slot.name == name)
+    }
+}
+
+pub(crate) fn slot_path(dir: &Path, slot: &str) -> PathBuf {
+    dir.join(slot).with_extension(SLOT_EXTENSION)
+}
+
+/// Slot names end up as file names, so only allow a conservative set of characters.
+pub(crate) fn is_valid_slot_name(slot: &str) -> bool {
+    !slot.is_empty()
+        && slot.len() <= 64
+        && slot
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == ' ')
+}
+
+/// Reads all save slots in `dir`, sorted from newest to oldest.
+/// Files that cannot be parsed are skipped with a warning so that a single corrupt save doesn't hide the others.
+pub(crate) fn list_slots(dir: &Path) -> anyhow::Result<Vec<SaveSlot>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read save directory {}", dir.display()))?;
+    let mut slots = Vec::new();
+    for entry in entries {
+        let path = entry.context("Failed to read save directory entry")?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(SLOT_EXTENSION) {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        match read_slot(dir, name) {
+            Ok(model) => slots.push(SaveSlot {
+                name: name.to_string(),
+                metadata: model.metadata,
+            }),
+            Err(e) => warn!("Skipping unreadable save file {}: {e:?}", path.display()),
+        }
+    }
+    slots.sort_by(|a, b| {
+        b.metadata
+            .timestamp
+            .cmp(&a.metadata.timestamp)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(slots)
+}
+
+pub(crate) fn read_slot(dir: &Path, slot: &str) -> anyhow::Result<SaveModel> {
+    let path = slot_path(dir, slot);
+    let serialized = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read save file {}", path.display()))?;
+    SaveModel::from_ron(&serialized)
+        .with_context(|| format!("Failed to load save file {}", path.display()))
+}
+
+pub(crate) fn write_slot(dir: &Path, slot: &str, model: &SaveModel) -> anyhow::Result<()> {
+    anyhow::ensure!(is_valid_slot_name(slot), "Invalid save slot name {slot:?}");
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create save directory {}", dir.display()))?;
+    let path = slot_path(dir, slot);
+    fs::write(&path, model.to_ron()?)
+        .with_context(|| format!("Failed to write save file {}", path.display()))
+}
+
+pub(crate) fn delete_slot(dir: &Path, slot: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(is_valid_slot_name(slot), "Invalid save slot name {slot:?}");
+    let path = slot_path(dir, slot);
+    fs::remove_file(&path).with_context(|| format!("Failed to delete save file {}", path.display()))
+}
+
+/// Formats a Unix timestamp as `YYYY-MM-DD HH:MM` (UTC) without pulling in a date library.
+pub(crate) fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86_400) as i64;
+    let seconds_of_day = timestamp % 86_400;
+    // See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        seconds_of_day / 3600,
+        (seconds_of_day % 3600) / 60
+    )
+}
+
+pub(crate) fn format_play_time(play_time: Duration) -> String {
+    let total_minutes = play_time.as_secs() / 60;
+    format!("{}h {:02}m", total_minutes / 60, total_minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_system_interaction::save::tests::save_model;
+    use crate::test_harness::TempDir;
+
+    fn write_slot_at(dir: &Path, slot: &str, timestamp: u64) {
+        let mut model = save_model();
+        model.metadata.timestamp = timestamp;
+        write_slot(dir, slot, &model).unwrap();
+    }
+
+    fn names(slots: &[SaveSlot]) -> Vec<&str> {
+        slots.iter().map(|slot| slot.name.as_str()).collect()
+    }
+
+    #[test]
+    fn slots_are_listed_newest_first() {
+        let dir = TempDir::new("sorted");
+        write_slot_at(&dir.0, "old", 100);
+        write_slot_at(&dir.0, "newest", 300);
+        write_slot_at(&dir.0, "middle", 200);
+
+        let slots = list_slots(&dir.0).unwrap();
+
+        assert_eq!(names(&slots), ["newest", "middle", "old"]);
+        assert_eq!(slots[0].metadata.timestamp, 300);
+    }
+
+    #[test]
+    fn listing_skips_other_and_corrupt_files() {
+        let dir = TempDir::new("skip");
+        write_slot_at(&dir.0, "valid", 100);
+        fs::write(dir.0.join("notes.txt"), "not a save").unwrap();
+        fs::write(dir.0.join("broken.ron"), "(version: ").unwrap();
+
+        let slots = list_slots(&dir.0).unwrap();
+
+        assert_eq!(names(&slots), ["valid"]);
+    }
+
+    #[test]
+    fn missing_directory_has_no_slots() {
+        let dir = TempDir::new("missing");
+        let missing = dir.0.join("does-not-exist");
+
+        assert_eq!(list_slots(&missing).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn invalid_slot_names_are_rejected() {
+        assert!(is_valid_slot_name("Slot 1"));
+        assert!(is_valid_slot_name("quick_save-2"));
+        assert!(!is_valid_slot_name(""));
+        assert!(!is_valid_slot_name("../escape"));
+        assert!(!is_valid_slot_name("dir/slot"));
+        assert!(!is_valid_slot_name(&"a".repeat(65)));
+
+        let dir = TempDir::new("invalid");
+        assert!(write_slot(&dir.0, "../escape", &save_model()).is_err());
+        assert!(delete_slot(&dir.0, "../escape").is_err());
+    }
+
+    #[test]
+    fn deleted_slot_is_no_longer_listed() {
+        let dir = TempDir::new("delete");
+        write_slot_at(&dir.0, "kept", 100);
+        write_slot_at(&dir.0, "deleted", 200);
+
+        delete_slot(&dir.0, "deleted").unwrap();
+
+        assert_eq!(names(&list_slots(&dir.0).unwrap()), ["kept"]);
+    }
+
+    #[test]
+    fn timestamps_are_formatted_as_utc() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29 00:00");
+        assert_eq!(format_timestamp(1_700_000_000), "2023-11-14 22:13");
+    }
+}
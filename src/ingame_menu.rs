@@ -1,4 +1,5 @@
 use crate::{
+    file_system_interaction::save::{is_valid_slot_name, GameSaveRequest, SaveSlots},
     player_control::actions::{ActionsFrozen, UiAction},
     GameState,
 };
@@ -7,6 +8,7 @@ use bevy_egui::{egui, EguiContexts};
 use leafwing_input_manager::prelude::ActionState;
 
 /// Handles the pause menu accessed while playing the game via ESC.
+/// The pause menu also allows saving the game into a named slot.
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(Update, handle_pause.run_if(in_state(GameState::Playing)));
 }
@@ -19,6 +21,10 @@ fn handle_pause(
     mut actions_frozen: ResMut<ActionsFrozen>,
     mut egui_contexts: EguiContexts,
     mut paused: Local<bool>,
+    save_slots: Res<SaveSlots>,
+    mut save_requests: EventWriter<GameSaveRequest>,
+    mut slot_name: Local<String>,
+    mut confirm_overwrite: Local<Option<String>>,
 ) {
     use crate::physics_time::PhysicsTimeExt;
 
@@ -55,7 +61,15 @@ fn handle_pause(
                 ui.separator();
                 ui.label("Press ESC to resume");
 
-                ui.add_space(100.0);
+                ui.add_space(50.0);
+                show_save_section(
+                    ui,
+                    &save_slots,
+                    &mut save_requests,
+                    &mut slot_name,
+                    &mut confirm_overwrite,
+                );
+                ui.add_space(50.0);
 
                 if ui.button("Quit Game").clicked() {
                     app_exit_events.send(AppExit);
@@ -63,3 +77,43 @@ fn handle_pause(
             });
         });
 }
+
+fn show_save_section(
+    ui: &mut egui::Ui,
+    save_slots: &SaveSlots,
+    save_requests: &mut EventWriter<GameSaveRequest>,
+    slot_name: &mut String,
+    confirm_overwrite: &mut Option<String>,
+) {
+    if let Some(slot) = confirm_overwrite.clone() {
+        ui.label(format!("Overwrite save \"{slot}\"?"));
+        if ui.button("Overwrite").clicked() {
+            save_requests.send(GameSaveRequest { slot });
+            *confirm_overwrite = None;
+        }
+        if ui.button("Cancel").clicked() {
+            *confirm_overwrite = None;
+        }
+        return;
+    }
+
+    ui.label("Save slot name");
+    ui.text_edit_singleline(slot_name);
+    let slot = slot_name.trim().to_string();
+    let is_valid = is_valid_slot_name(&slot);
+    if ui
+        .add_enabled(is_valid, egui::Button::new("Save Game"))
+        .clicked()
+    {
+        if save_slots.contains(&slot) {
+            *confirm_overwrite = Some(slot);
+        } else {
+            save_requests.send(GameSaveRequest { slot });
+        }
+    }
+    for existing in save_slots.0.iter() {
+        if ui.button(format!("Overwrite \"{}\"", existing.name)).clicked() {
+            *confirm_overwrite = Some(existing.name.clone());
+        }
+    }
+}
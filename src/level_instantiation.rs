@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
 mod blender_workflow;
-mod map;
+pub(crate) mod map;
 pub(crate) mod on_spawn;
 
 /// Handles creation of levels and objects. Split into the following sub-plugins:
@@ -11,3 +11,10 @@ pub(crate) mod on_spawn;
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((map::plugin, on_spawn::plugin, blender_workflow::plugin));
 }
+
+/// Spawns objects from their marker components, but doesn't load any level.
+/// Levels have to be built procedurally instead.
+#[cfg(test)]
+pub(super) fn headless_plugin(app: &mut App) {
+    app.add_plugins(on_spawn::headless_plugin);
+}
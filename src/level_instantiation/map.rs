@@ -7,6 +7,9 @@ use bevy::{gltf::Gltf, prelude::*};
 use bevy_atmosphere::prelude::*;
 use bevy_dolly::prelude::*;
 
+/// Human-readable name of the level stored in [`GltfAssets::level`].
+pub(crate) const LEVEL_NAME: &str = "World";
+
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(OnEnter(GameState::Playing), spawn_level);
 }
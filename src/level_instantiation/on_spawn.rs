@@ -1,8 +1,11 @@
 use bevy::prelude::*;
 
-pub(crate) use self::{ground::Ground, npc::Npc, player::Player};
+pub(crate) use self::{
+    collider::ColliderError, dynamic_prop::DynamicProp, ground::Ground, npc::Npc, player::Player,
+};
 
 mod collider;
+mod dynamic_prop;
 mod grass;
 mod ground;
 mod hidden;
@@ -15,13 +18,16 @@ mod util;
 /// The reason you will want to do this is that the Blender workflow allows you to add marker components to objects in Blender.
 /// These marker components are then used to spawn the rest of the components or modify other existing components in Bevy through code.
 pub(super) fn plugin(app: &mut App) {
+    app.add_plugins((headless_plugin, grass::plugin, ground::plugin, orb::plugin));
+}
+
+/// The parts of [`plugin`] that don't need any rendering.
+pub(super) fn headless_plugin(app: &mut App) {
     app.add_plugins((
-        grass::plugin,
-        ground::plugin,
-        orb::plugin,
         player::plugin,
         npc::plugin,
         hidden::plugin,
         collider::plugin,
+        dynamic_prop::plugin,
     ));
 }
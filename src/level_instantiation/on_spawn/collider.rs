@@ -1,60 +1,278 @@
-use crate::util::error;
+use self::cache::ColliderCache;
+use crate::movement::physics::{CollisionLayer, CollisionLayers};
 use crate::GameSystemSet;
 use anyhow::Context;
-use bevy::prelude::*;
+use bevy::{prelude::*, render::primitives::Aabb};
 use bevy_rapier3d::prelude::{Collider as RapierCollider, *};
 use oxidized_navigation::NavMeshAffector;
 use serde::{Deserialize, Serialize};
 use std::iter;
 
-#[derive(Debug, Clone, Eq, PartialEq, Component, Reflect, Serialize, Deserialize, Default)]
+mod cache;
+
+/// Marks an object from Blender whose meshes should get colliders.
+/// All fields are optional, so a bare `Collider` results in a static trimesh terrain collider.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize, Default)]
 #[reflect(Component, Serialize, Deserialize)]
-struct Collider;
+#[serde(default)]
+pub(crate) struct Collider {
+    #[reflect(default)]
+    pub(crate) shape: ColliderShape,
+    /// Uses rapier's default when not set
+    #[reflect(default)]
+    pub(crate) friction: Option<f32>,
+    /// Uses rapier's default when not set
+    #[reflect(default)]
+    pub(crate) restitution: Option<f32>,
+    /// Sensors detect overlaps, but don't push anything
+    #[reflect(default)]
+    pub(crate) sensor: bool,
+    /// Defaults to a sensor detecting the player for sensors, to props for dynamic rigid bodies and to terrain otherwise
+    #[reflect(default)]
+    pub(crate) collision_layers: Option<CollisionLayers>,
+}
+
+/// Added to meshes whose collider could not be built, so that a single broken mesh
+/// doesn't keep the rest of the level from getting colliders.
+#[derive(Debug, Clone, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub(crate) struct ColliderError {
+    pub(crate) reason: String,
+}
+
+/// How the collider is built from each mesh.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize, Default)]
+pub(crate) enum ColliderShape {
+    /// Exact, but expensive and hollow. Only use this for static geometry.
+    #[default]
+    TriMesh,
+    /// Cheap and solid, but fills in all concave parts.
+    ConvexHull,
+    /// Approximates concave meshes by multiple convex hulls. Slow to build.
+    ConvexDecomposition {
+        /// Maximum allowed concavity of each part, relative to the mesh size
+        concavity: f32,
+        /// Voxel resolution used for the decomposition
+        resolution: u32,
+        max_convex_hulls: u32,
+    },
+    /// Box fitted to the mesh's bounding box
+    Cuboid,
+    /// Sphere enclosing the mesh's bounding box
+    Ball,
+    /// Capsule along the longest axis of the mesh's bounding box
+    Capsule,
+}
+
+/// The [`ColliderShape`]s that are fitted to the bounding box of a mesh instead of being built from its triangles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AabbShape {
+    Cuboid,
+    Ball,
+    Capsule,
+}
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<Collider>().add_systems(
-        Update,
-        spawn.pipe(error).in_set(GameSystemSet::ColliderSpawn),
-    );
+    app.register_type::<Collider>()
+        .register_type::<ColliderShape>()
+        .register_type::<ColliderError>()
+        .add_plugins(cache::plugin)
+        .add_systems(Update, spawn.in_set(GameSystemSet::ColliderSpawn));
 }
 
 fn spawn(
-    collider_marker: Query<Entity, With<Collider>>,
+    collider_marker: Query<(Entity, &Collider, Option<&RigidBody>)>,
     mut commands: Commands,
     children: Query<&Children>,
     meshes: Res<Assets<Mesh>>,
     mesh_handles: Query<&Handle<Mesh>>,
-) -> anyhow::Result<()> {
+    names: Query<&Name>,
+    mut cache: ResMut<ColliderCache>,
+) {
     #[cfg(feature = "tracing")]
     let _span = info_span!("read_colliders").entered();
-    for parent in collider_marker.iter() {
+    for (parent, marker, rigid_body) in collider_marker.iter() {
+        let collision_layers = marker.collision_layers.clone().unwrap_or_else(|| {
+            if marker.sensor {
+                CollisionLayers::new(CollisionLayer::Sensor, CollisionLayer::Player)
+            } else if rigid_body == Some(&RigidBody::Dynamic) {
+                CollisionLayers::prop()
+            } else {
+                CollisionLayers::terrain()
+            }
+        });
         for child in iter::once(parent).chain(children.iter_descendants(parent)) {
             let Ok(mesh_handle) = mesh_handles.get(child) else {
                 continue;
             };
-            // Unwrap cannot fail: we already load all the meshes at startup.
-            let mesh = meshes.get(mesh_handle).unwrap();
-            let collider = RapierCollider::from_bevy_mesh(
-                mesh,
-                &bevy_rapier3d::prelude::ComputedColliderShape::TriMesh,
-            )
-            .context("Failed to create collider from mesh")?;
-            commands.entity(child).insert((
+            let collider = meshes
+                .get(mesh_handle)
+                .context("Mesh is not loaded")
+                .and_then(|mesh| {
+                    cache.get_or_create(mesh_handle.id(), mesh, marker.shape, || {
+                        create_collider(mesh, marker.shape)
+                    })
+                });
+            let mut entity_commands = commands.entity(child);
+            let collider = match collider {
+                Ok(collider) => collider,
+                Err(e) => {
+                    // Logged only once, since the marker is removed below either way
+                    let name = names.get(child).map(Name::as_str).unwrap_or("unnamed");
+                    error!("Failed to create collider for {child:?} ({name}): {e:#}");
+                    entity_commands.insert(ColliderError {
+                        reason: format!("{e:#}"),
+                    });
+                    continue;
+                }
+            };
+            entity_commands.insert((
                 collider,
-                // CollisionLayers::new(
-                //     [CollisionLayer::Terrain, CollisionLayer::CameraObstacle],
-                //     [CollisionLayer::Character],
-                // ),
-                CollisionGroups::new(Group::GROUP_3 | Group::GROUP_4, Group::GROUP_2),
+                collision_layers.clone(),
                 ActiveEvents::COLLISION_EVENTS,
                 ActiveCollisionTypes::default(),
-                NavMeshAffector,
             ));
+            if let Some(friction) = marker.friction {
+                entity_commands.insert(Friction::coefficient(friction));
+            }
+            if let Some(restitution) = marker.restitution {
+                entity_commands.insert(Restitution::coefficient(restitution));
+            }
+            if marker.sensor {
+                entity_commands.insert(Sensor);
+            } else if matches!(rigid_body, None | Some(RigidBody::Fixed)) {
+                // Moving bodies would constantly invalidate the navmesh
+                entity_commands.insert(NavMeshAffector);
+            }
         }
-        commands
-            .entity(parent)
-            .remove::<Collider>()
-            .insert(RigidBody::Fixed);
+        let mut parent_commands = commands.entity(parent);
+        parent_commands.remove::<Collider>();
+        // Objects that already have a rigid body, e.g. dynamic props, keep it
+        if rigid_body.is_none() {
+            parent_commands.insert(RigidBody::Fixed);
+        }
+    }
+}
+
+fn create_collider(mesh: &Mesh, shape: ColliderShape) -> anyhow::Result<RapierCollider> {
+    let computed_shape = match shape {
+        ColliderShape::TriMesh => ComputedColliderShape::TriMesh,
+        ColliderShape::ConvexHull => ComputedColliderShape::ConvexHull,
+        ColliderShape::ConvexDecomposition {
+            concavity,
+            resolution,
+            max_convex_hulls,
+        } => ComputedColliderShape::ConvexDecomposition(VHACDParameters {
+            concavity,
+            resolution,
+            max_convex_hulls,
+            ..default()
+        }),
+        ColliderShape::Cuboid => return fit_to_aabb(mesh, AabbShape::Cuboid),
+        ColliderShape::Ball => return fit_to_aabb(mesh, AabbShape::Ball),
+        ColliderShape::Capsule => return fit_to_aabb(mesh, AabbShape::Capsule),
+    };
+    RapierCollider::from_bevy_mesh(mesh, &computed_shape)
+        .context("Failed to create collider from mesh")
+}
+
+fn fit_to_aabb(mesh: &Mesh, shape: AabbShape) -> anyhow::Result<RapierCollider> {
+    let aabb = mesh
+        .compute_aabb()
+        .context("Failed to compute bounding box of mesh")?;
+    Ok(create_collider_from_aabb(&aabb, shape))
+}
+
+fn create_collider_from_aabb(aabb: &Aabb, shape: AabbShape) -> RapierCollider {
+    let center = Vec3::from(aabb.center);
+    let half_extents = Vec3::from(aabb.half_extents);
+    let collider = match shape {
+        AabbShape::Cuboid => RapierCollider::cuboid(half_extents.x, half_extents.y, half_extents.z),
+        AabbShape::Ball => RapierCollider::ball(half_extents.length()),
+        AabbShape::Capsule => {
+            let length = half_extents.max_element();
+            let axis = if length == half_extents.x {
+                Vec3::X
+            } else if length == half_extents.y {
+                Vec3::Y
+            } else {
+                Vec3::Z
+            };
+            let radius = (half_extents - axis * half_extents).max_element();
+            let half_height = (length - radius).max(0.);
+            // The endpoints already include the offset
+            return RapierCollider::capsule(
+                center - axis * half_height,
+                center + axis * half_height,
+                radius,
+            );
+        }
+    };
+    if center.length_squared() < 1e-6 {
+        collider
+    } else {
+        RapierCollider::compound(vec![(center, Quat::IDENTITY, collider)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TestApp;
+    use bevy::render::{render_asset::RenderAssetUsages, render_resource::PrimitiveTopology};
+
+    #[test]
+    fn capsule_follows_longest_axis_of_aabb() {
+        let aabb = Aabb::from_min_max(Vec3::new(-0.5, 0., -0.5), Vec3::new(0.5, 4., 0.5));
+
+        let collider = create_collider_from_aabb(&aabb, AabbShape::Capsule);
+
+        let capsule = collider.as_capsule().expect("Collider should be a capsule");
+        assert_eq!(capsule.radius(), 0.5);
+        assert_eq!(capsule.segment().a(), Vec3::new(0., 0.5, 0.));
+        assert_eq!(capsule.segment().b(), Vec3::new(0., 3.5, 0.));
+    }
+
+    #[test]
+    fn ball_encloses_aabb() {
+        let aabb = Aabb::from_min_max(Vec3::new(-1., -2., -2.), Vec3::new(1., 2., 2.));
+
+        let collider = create_collider_from_aabb(&aabb, AabbShape::Ball);
+
+        let ball = collider.as_ball().expect("Collider should be a ball");
+        assert_eq!(ball.radius(), 3.);
+    }
+
+    #[test]
+    fn broken_mesh_does_not_block_other_colliders() {
+        let mut app = TestApp::new();
+        let mut meshes = app.app.world.resource_mut::<Assets<Mesh>>();
+        let cube = meshes.add(Cuboid::default());
+        let empty = meshes.add(Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        ));
+        let broken = app
+            .app
+            .world
+            .spawn((Collider::default(), empty, SpatialBundle::default()))
+            .id();
+        let working = app
+            .app
+            .world
+            .spawn((Collider::default(), cube, SpatialBundle::default()))
+            .id();
+
+        app.tick();
+
+        let error = app.app.world.get::<ColliderError>(broken);
+        assert!(
+            error.is_some(),
+            "Broken mesh should be tagged with an error"
+        );
+        assert!(app.app.world.get::<RapierCollider>(broken).is_none());
+        assert!(app.app.world.get::<RapierCollider>(working).is_some());
+        assert!(app.app.world.get::<Collider>(broken).is_none());
     }
-    Ok(())
 }
@@ -0,0 +1,455 @@
+use super::ColliderShape;
+use crate::file_system_interaction::config::GameConfig;
+use crate::util::error;
+use crate::{GameState, GameSystemSet};
+use anyhow::Context;
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::prelude::{Collider as RapierCollider, *};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Lives next to `assets/scenes/`. It is generated and thus ignored by git, but can be shipped with the assets of a release.
+const COLLIDER_CACHE_PATH: &str = "assets/collider_cache.bin";
+/// Bump this when the way colliders are built changes, so that stale caches are discarded.
+const COLLIDER_CACHE_VERSION: u32 = 2;
+/// The cache is written once no new colliders were built for this long, so that spawning a level writes it only once.
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+/// Colliders that were not used for this many saves in a row are dropped,
+/// so that the cache doesn't keep growing with meshes that were removed from the levels.
+const MAX_UNUSED_SAVES: u32 = 8;
+
+/// Shares colliders between meshes with the same content and [`ColliderShape`],
+/// so that levels don't rebuild their trimeshes on every load and duplicated blueprints build them only once.
+/// Persisted to [`ColliderCachePath`] when `physics.persist_collider_cache` is set in the [`GameConfig`].
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ColliderCache>()
+        .init_resource::<ColliderCachePath>()
+        .add_systems(
+            Update,
+            (
+                (forget_modified_meshes, load_collider_cache.pipe(error))
+                    .before(GameSystemSet::ColliderSpawn),
+                save_settled_collider_cache
+                    .pipe(error)
+                    .after(GameSystemSet::ColliderSpawn),
+            )
+                .run_if(resource_exists::<GameConfig>),
+        )
+        .add_systems(
+            OnExit(GameState::Playing),
+            save_collider_cache
+                .pipe(error)
+                .run_if(resource_exists::<GameConfig>),
+        );
+}
+
+/// Where the collider cache is persisted.
+#[derive(Debug, Clone, PartialEq, Eq, Resource)]
+pub(super) struct ColliderCachePath(pub(super) PathBuf);
+
+impl Default for ColliderCachePath {
+    fn default() -> Self {
+        Self(PathBuf::from(COLLIDER_CACHE_PATH))
+    }
+}
+
+#[derive(Debug, Resource, Default)]
+pub(super) struct ColliderCache {
+    colliders: HashMap<u64, RapierCollider>,
+    /// Content hashes of the meshes that were already seen, so that each mesh is only hashed once
+    mesh_hashes: HashMap<AssetId<Mesh>, u64>,
+    /// Colliders requested since the last save
+    used: HashSet<u64>,
+    /// Number of saves in a row the colliders that are not in here were unused for
+    unused_saves: HashMap<u64, u32>,
+    loaded_from_disk: bool,
+    /// Colliders were added since the cache was last written to disk
+    dirty: bool,
+    /// Time since the last collider was added
+    settled_for: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ColliderCacheFile {
+    version: u32,
+    shapes: Vec<CachedShape>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedShape {
+    key: u64,
+    shape: SharedShape,
+    unused_saves: u32,
+}
+
+impl ColliderCache {
+    /// Returns the cached collider for `mesh` or builds it with `create` on a cache miss.
+    pub(super) fn get_or_create(
+        &mut self,
+        mesh_id: AssetId<Mesh>,
+        mesh: &Mesh,
+        shape: ColliderShape,
+        create: impl FnOnce() -> anyhow::Result<RapierCollider>,
+    ) -> anyhow::Result<RapierCollider> {
+        let mesh_hash = *self
+            .mesh_hashes
+            .entry(mesh_id)
+            .or_insert_with(|| hash_mesh(mesh));
+        let key = hash_shape(mesh_hash, shape);
+        self.used.insert(key);
+        if let Some(collider) = self.colliders.get(&key) {
+            return Ok(collider.clone());
+        }
+        let collider = create()?;
+        self.colliders.insert(key, collider.clone());
+        self.dirty = true;
+        self.settled_for = Duration::ZERO;
+        Ok(collider)
+    }
+
+    /// Ages the colliders that were not used since the last save, drops the ones that were unused for too long
+    /// and returns what is left.
+    fn prepare_save(&mut self) -> ColliderCacheFile {
+        let used = std::mem::take(&mut self.used);
+        let unused_saves = &mut self.unused_saves;
+        self.colliders.retain(|key, _| {
+            if used.contains(key) {
+                unused_saves.remove(key);
+                return true;
+            }
+            let saves = unused_saves.entry(*key).or_default();
+            *saves += 1;
+            if *saves > MAX_UNUSED_SAVES {
+                unused_saves.remove(key);
+                return false;
+            }
+            true
+        });
+        ColliderCacheFile {
+            version: COLLIDER_CACHE_VERSION,
+            shapes: self
+                .colliders
+                .iter()
+                .map(|(key, collider)| CachedShape {
+                    key: *key,
+                    shape: collider.raw.clone(),
+                    unused_saves: self.unused_saves.get(key).copied().unwrap_or_default(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn forget_modified_meshes(
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut cache: ResMut<ColliderCache>,
+) {
+    for event in mesh_events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            cache.mesh_hashes.remove(id);
+        }
+    }
+}
+
+fn load_collider_cache(
+    config: Res<GameConfig>,
+    cache_path: Res<ColliderCachePath>,
+    mut cache: ResMut<ColliderCache>,
+) -> anyhow::Result<()> {
+    if !config.physics.persist_collider_cache || cache.loaded_from_disk {
+        return Ok(());
+    }
+    cache.loaded_from_disk = true;
+    let path = cache_path.0.as_path();
+    if !path.exists() {
+        return Ok(());
+    }
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open collider cache {}", path.display()))?;
+    let cache_file: ColliderCacheFile = bincode::deserialize_from(BufReader::new(file))
+        .with_context(|| format!("Failed to read collider cache {}", path.display()))?;
+    if cache_file.version != COLLIDER_CACHE_VERSION {
+        info!(
+            "Discarding collider cache with version {}, expected {COLLIDER_CACHE_VERSION}",
+            cache_file.version
+        );
+        return Ok(());
+    }
+    info!("Loaded {} colliders from cache", cache_file.shapes.len());
+    for cached in cache_file.shapes {
+        if cached.unused_saves > 0 {
+            cache.unused_saves.insert(cached.key, cached.unused_saves);
+        }
+        cache
+            .colliders
+            .insert(cached.key, RapierCollider::from(cached.shape));
+    }
+    Ok(())
+}
+
+/// Waits until no new colliders were built for [`SAVE_DEBOUNCE`], which usually means that the level finished spawning.
+fn save_settled_collider_cache(
+    time: Res<Time<Real>>,
+    config: Res<GameConfig>,
+    cache_path: Res<ColliderCachePath>,
+    mut cache: ResMut<ColliderCache>,
+) -> anyhow::Result<()> {
+    if !cache.dirty {
+        return Ok(());
+    }
+    cache.settled_for += time.delta();
+    if cache.settled_for < SAVE_DEBOUNCE {
+        return Ok(());
+    }
+    write_collider_cache(&config, &cache_path.0, &mut cache)
+}
+
+fn save_collider_cache(
+    config: Res<GameConfig>,
+    cache_path: Res<ColliderCachePath>,
+    mut cache: ResMut<ColliderCache>,
+) -> anyhow::Result<()> {
+    write_collider_cache(&config, &cache_path.0, &mut cache)
+}
+
+/// Writes to a temporary file first, so that a crash while writing doesn't leave a truncated cache behind.
+fn write_collider_cache(
+    config: &GameConfig,
+    path: &Path,
+    cache: &mut ColliderCache,
+) -> anyhow::Result<()> {
+    if !config.physics.persist_collider_cache || !cache.dirty {
+        return Ok(());
+    }
+    cache.dirty = false;
+    let cache_file = cache.prepare_save();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory {}", dir.display()))?;
+    }
+    let temp_path = path.with_extension("bin.tmp");
+    let file = File::create(&temp_path)
+        .with_context(|| format!("Failed to create collider cache {}", temp_path.display()))?;
+    let mut writer = BufWriter::new(file);
+    bincode::serialize_into(&mut writer, &cache_file)
+        .with_context(|| format!("Failed to write collider cache {}", temp_path.display()))?;
+    writer
+        .into_inner()
+        .with_context(|| format!("Failed to write collider cache {}", temp_path.display()))?;
+    fs::rename(&temp_path, path)
+        .with_context(|| format!("Failed to replace collider cache {}", path.display()))?;
+    Ok(())
+}
+
+/// FNV-1a, because the standard library's hashers are not guaranteed to be stable across Rust versions,
+/// which would silently invalidate the cache on disk.
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+}
+
+/// Hashes everything a collider is built from: the vertex positions and the indices.
+fn hash_mesh(mesh: &Mesh) -> u64 {
+    let mut hasher = StableHasher::new();
+    if let Some(positions) = mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+        hasher.write(positions.get_bytes());
+    }
+    if let Some(indices) = mesh.indices() {
+        for index in indices.iter() {
+            hasher.write_u32(index as u32);
+        }
+    }
+    hasher.0
+}
+
+fn hash_shape(mesh_hash: u64, shape: ColliderShape) -> u64 {
+    let mut hasher = StableHasher::new();
+    hasher.write(&mesh_hash.to_le_bytes());
+    match shape {
+        ColliderShape::TriMesh => hasher.write_u32(0),
+        ColliderShape::ConvexHull => hasher.write_u32(1),
+        ColliderShape::ConvexDecomposition {
+            concavity,
+            resolution,
+            max_convex_hulls,
+        } => {
+            hasher.write_u32(2);
+            hasher.write_f32(concavity);
+            hasher.write_u32(resolution);
+            hasher.write_u32(max_convex_hulls);
+        }
+        ColliderShape::Cuboid => hasher.write_u32(3),
+        ColliderShape::Ball => hasher.write_u32(4),
+        ColliderShape::Capsule => hasher.write_u32(5),
+    }
+    hasher.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Collider as ColliderMarker;
+    use super::*;
+    use crate::physics_time::DEFAULT_TIMESTEP;
+    use crate::test_harness::{TempDir, TestApp};
+    use bevy::render::{
+        mesh::Indices, render_asset::RenderAssetUsages, render_resource::PrimitiveTopology,
+    };
+
+    fn triangle(height: f32) -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            vec![[0., 0., 0.], [1., 0., 0.], [0., height, 0.]],
+        )
+        .with_inserted_indices(Indices::U32(vec![0, 1, 2]))
+    }
+
+    #[test]
+    fn meshes_with_same_content_share_a_collider() {
+        let mut cache = ColliderCache::default();
+        let mut builds = 0;
+        let mut get = |cache: &mut ColliderCache, id: u128, mesh: &Mesh| {
+            cache
+                .get_or_create(
+                    AssetId::Uuid {
+                        uuid: bevy::utils::Uuid::from_u128(id),
+                    },
+                    mesh,
+                    ColliderShape::TriMesh,
+                    || {
+                        builds += 1;
+                        Ok(RapierCollider::ball(1.))
+                    },
+                )
+                .unwrap();
+        };
+
+        get(&mut cache, 1, &triangle(1.));
+        get(&mut cache, 2, &triangle(1.));
+        get(&mut cache, 3, &triangle(2.));
+
+        assert_eq!(builds, 2);
+    }
+
+    fn read_cache_file(path: &Path) -> ColliderCacheFile {
+        let file = File::open(path).expect("Collider cache was not written");
+        bincode::deserialize_from(BufReader::new(file)).unwrap()
+    }
+
+    #[test]
+    fn unused_colliders_are_dropped_after_max_unused_saves() {
+        let mut cache = ColliderCache::default();
+        cache.colliders.insert(1, RapierCollider::ball(1.));
+        cache.colliders.insert(2, RapierCollider::ball(2.));
+        cache.unused_saves.insert(2, MAX_UNUSED_SAVES - 1);
+
+        let file = cache.prepare_save();
+        let mut shapes: Vec<_> = file
+            .shapes
+            .iter()
+            .map(|shape| (shape.key, shape.unused_saves))
+            .collect();
+        shapes.sort();
+        assert_eq!(shapes, [(1, 1), (2, MAX_UNUSED_SAVES)]);
+
+        cache.used.insert(1);
+        let file = cache.prepare_save();
+        let shapes: Vec<_> = file
+            .shapes
+            .iter()
+            .map(|shape| (shape.key, shape.unused_saves))
+            .collect();
+        assert_eq!(shapes, [(1, 0)]);
+        assert!(!cache.colliders.contains_key(&2));
+    }
+
+    #[test]
+    fn cache_is_written_once_level_settled() {
+        let dir = TempDir::new("collider-cache");
+        let path = dir.0.join("collider_cache.bin");
+        let mut app = TestApp::with_config();
+        assert!(
+            app.app
+                .world
+                .resource::<GameConfig>()
+                .physics
+                .persist_collider_cache
+        );
+        app.app.insert_resource(ColliderCachePath(path.clone()));
+        let cube = app
+            .app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::default());
+        app.app
+            .world
+            .spawn((ColliderMarker::default(), cube, SpatialBundle::default()));
+
+        app.tick();
+        assert!(
+            !path.exists(),
+            "Cache should only be written once no colliders were built for a while"
+        );
+
+        let settle_ticks =
+            (SAVE_DEBOUNCE.as_secs_f32() / DEFAULT_TIMESTEP.as_secs_f32()) as u32 + 1;
+        app.ticks(settle_ticks);
+        let file = read_cache_file(&path);
+        assert_eq!(file.version, COLLIDER_CACHE_VERSION);
+        assert_eq!(file.shapes.len(), 1);
+        assert!(!path.with_extension("bin.tmp").exists());
+        assert!(!app.app.world.resource::<ColliderCache>().dirty);
+    }
+
+    #[test]
+    fn cache_is_not_written_when_not_persisted() {
+        let dir = TempDir::new("collider-cache-off");
+        let path = dir.0.join("collider_cache.bin");
+        let mut app = TestApp::with_config();
+        app.config_mut().physics.persist_collider_cache = false;
+        app.app.insert_resource(ColliderCachePath(path.clone()));
+        let cube = app
+            .app
+            .world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::default());
+        app.app
+            .world
+            .spawn((ColliderMarker::default(), cube, SpatialBundle::default()));
+
+        let settle_ticks =
+            (SAVE_DEBOUNCE.as_secs_f32() / DEFAULT_TIMESTEP.as_secs_f32()) as u32 + 1;
+        app.ticks(settle_ticks);
+
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{GameState, GameSystemSet};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{Collider as RapierCollider, *};
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+/// Marks an object from Blender as a dynamic rigid body, e.g. a crate or a ball the player can push around.
+/// Combine it with a `Collider` marker to give the object a shape; convex shapes work best.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct DynamicProp {
+    /// Total mass in kg, spread evenly over all colliders. Takes precedence over `density`.
+    #[reflect(default)]
+    pub(crate) mass: Option<f32>,
+    /// Density of the colliders in kg/m³, used when no `mass` is set
+    #[reflect(default)]
+    pub(crate) density: f32,
+    #[reflect(default)]
+    pub(crate) linear_damping: f32,
+    #[reflect(default)]
+    pub(crate) angular_damping: f32,
+    /// Continuous collision detection keeps small, fast props from tunneling through thin walls
+    #[reflect(default)]
+    pub(crate) ccd: bool,
+    /// Sleeping props don't move until something touches them
+    #[reflect(default)]
+    pub(crate) start_asleep: bool,
+}
+
+impl Default for DynamicProp {
+    fn default() -> Self {
+        Self {
+            mass: None,
+            density: 1.,
+            linear_damping: 0.,
+            angular_damping: 0.,
+            ccd: false,
+            start_asleep: true,
+        }
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DynamicProp>().add_systems(
+        Update,
+        (
+            spawn.before(GameSystemSet::ColliderSpawn),
+            apply_mass.after(GameSystemSet::ColliderSpawn),
+        )
+            .run_if(in_state(GameState::Playing)),
+    );
+}
+
+/// Runs before the colliders are spawned, so that they know they belong to a dynamic body.
+fn spawn(props: Query<(Entity, &DynamicProp), Added<DynamicProp>>, mut commands: Commands) {
+    for (entity, prop) in props.iter() {
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((
+            RigidBody::Dynamic,
+            Velocity::default(),
+            ExternalImpulse::default(),
+            Damping {
+                linear_damping: prop.linear_damping,
+                angular_damping: prop.angular_damping,
+            },
+            Sleeping {
+                sleeping: prop.start_asleep,
+                ..default()
+            },
+        ));
+        if prop.ccd {
+            entity_commands.insert(Ccd::enabled());
+        }
+    }
+}
+
+/// Distributes the mass over the colliders once they exist, then removes the marker.
+fn apply_mass(
+    props: Query<(Entity, &DynamicProp)>,
+    children: Query<&Children>,
+    colliders: Query<(), (With<RapierCollider>, Without<Sensor>)>,
+    mut commands: Commands,
+) {
+    for (entity, prop) in props.iter() {
+        let prop_colliders: Vec<_> = iter::once(entity)
+            .chain(children.iter_descendants(entity))
+            .filter(|&child| colliders.contains(child))
+            .collect();
+        if prop_colliders.is_empty() {
+            continue;
+        }
+        let mass_properties = match prop.mass {
+            Some(mass) => ColliderMassProperties::Mass(mass / prop_colliders.len() as f32),
+            None => ColliderMassProperties::Density(prop.density),
+        };
+        for collider in prop_colliders {
+            commands.entity(collider).insert(mass_properties);
+        }
+        commands.entity(entity).remove::<DynamicProp>();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_harness::{TestApp, TestEntity};
+    use bevy::prelude::*;
+
+    #[test]
+    fn player_pushes_crate() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        let prop = app.spawn(TestEntity::prop(0.5).at(Vec3::new(0., 0.5, -3.)));
+        app.ticks(120);
+        let start = app.translation(prop);
+
+        app.press(KeyCode::KeyW);
+        app.ticks(90);
+
+        let end = app.translation(prop);
+        assert!(
+            start.z - end.z > 0.5 && (end.x - start.x).abs() < 0.2,
+            "Crate should be pushed straight along -Z, but went from {start} to {end}"
+        );
+        assert!(
+            app.translation(player).z > end.z,
+            "Player should stay behind the crate at {end}, but is at {}",
+            app.translation(player)
+        );
+    }
+}
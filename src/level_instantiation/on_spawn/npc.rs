@@ -1,6 +1,10 @@
 use crate::{
     level_instantiation::on_spawn::player,
-    movement::character_controller::CharacterControllerBundle, GameState,
+    movement::{
+        character_controller::CharacterControllerBundle,
+        physics::{CollisionLayer, CollisionLayers},
+    },
+    GameState,
 };
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
@@ -28,8 +32,7 @@ fn spawn(follower: Query<(Entity, &Transform), Added<Npc>>, mut commands: Comman
                 parent.spawn((
                     Name::new("NPC Dialog Collider"),
                     Collider::cylinder(player::HEIGHT / 2., player::RADIUS * 5.),
-                    CollisionGroups::new(Group::GROUP_5, Group::GROUP_1),
-                    // CollisionLayers::new([CollisionLayer::Sensor], [CollisionLayer::Player]),
+                    CollisionLayers::new(CollisionLayer::Sensor, CollisionLayer::Player),
                     ActiveEvents::COLLISION_EVENTS,
                     ActiveCollisionTypes::default(),
                     Sensor,
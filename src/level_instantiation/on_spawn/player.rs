@@ -1,5 +1,5 @@
 use crate::{
-    movement::character_controller::CharacterControllerBundle,
+    movement::{character_controller::CharacterControllerBundle, physics::CollisionLayer},
     particles,
     player_control::actions::{
         create_player_action_input_manager_bundle, create_ui_action_input_manager_bundle,
@@ -8,7 +8,6 @@ use crate::{
 };
 use bevy::prelude::*;
 use bevy_hanabi::EffectAsset;
-use bevy_rapier3d::prelude::Group;
 use serde::{Deserialize, Serialize};
 
 pub(crate) const HEIGHT: f32 = 0.3; //0.4
@@ -30,7 +29,9 @@ fn spawn(
 ) {
     for (entity, transform) in player.iter() {
         let mut controller = CharacterControllerBundle::capsule(HEIGHT, RADIUS, transform.scale.y);
-        controller.collision_layers.memberships |= Group::GROUP_1; //CollisionLayer::Player;
+        controller
+            .collision_layers
+            .add_membership(CollisionLayer::Player);
 
         commands
             .entity(entity)
@@ -40,8 +41,8 @@ fn spawn(
                 create_ui_action_input_manager_bundle(),
             ))
             .with_children(|parent| {
-                let particle_bundle = particles::create_sprint_particle_bundle(&mut effects);
-                parent.spawn(particle_bundle);
+                parent.spawn(particles::create_sprint_particle_bundle(&mut effects));
+                parent.spawn(particles::create_splash_particle_bundle(&mut effects));
             });
     }
 }
@@ -23,6 +23,8 @@ pub(crate) mod particles;
 mod player_control;
 mod shader;
 mod system_set;
+#[cfg(test)]
+mod test_harness;
 pub(crate) mod util;
 mod world_interaction;
 
@@ -1,3 +1,7 @@
+use crate::file_system_interaction::save::{
+    delete_slot, format_play_time, format_timestamp, GameLoadRequest, PendingLoad, PlayTime,
+    SaveDirectory, SaveSlots,
+};
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_egui::{
@@ -12,11 +16,38 @@ use bevy_egui::{
 
 /// This plugin is responsible for the game menu
 /// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited.
+/// Besides starting a new game, the menu allows continuing from the newest save slot or picking one from a list.
 pub(super) fn plugin(app: &mut App) {
     app.add_systems(Update, setup_menu.run_if(in_state(GameState::Menu)));
 }
 
-fn setup_menu(mut egui_contexts: EguiContexts, mut next_state: ResMut<NextState<GameState>>) {
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+enum MenuScreen {
+    #[default]
+    Main,
+    LoadGame,
+    ConfirmDelete(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum MenuAction {
+    NewGame,
+    Load(String),
+    Delete(String),
+    Show(MenuScreen),
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    mut egui_contexts: EguiContexts,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut screen: Local<MenuScreen>,
+    mut save_slots: ResMut<SaveSlots>,
+    save_directory: Res<SaveDirectory>,
+    mut load_requests: EventWriter<GameLoadRequest>,
+    mut play_time: ResMut<PlayTime>,
+) {
+    let mut action = None;
     get_menu_panel().show(egui_contexts.ctx_mut(), |ui| {
         set_menu_style(ui.style_mut());
         ui.vertical_centered_justified(|ui| {
@@ -24,11 +55,105 @@ fn setup_menu(mut egui_contexts: EguiContexts, mut next_state: ResMut<NextState<
             ui.heading("Foxtrot");
             ui.separator();
             ui.add_space(50.);
-            if ui.button("Play").clicked() {
-                next_state.set(GameState::Playing);
-            }
+            action = match &*screen {
+                MenuScreen::Main => show_main_screen(ui, &save_slots),
+                MenuScreen::LoadGame => show_load_game_screen(ui, &save_slots),
+                MenuScreen::ConfirmDelete(slot) => show_confirm_delete_screen(ui, slot),
+            };
         })
     });
+
+    match action {
+        Some(MenuAction::NewGame) => {
+            commands.remove_resource::<PendingLoad>();
+            *play_time = default();
+            next_state.set(GameState::Playing);
+        }
+        Some(MenuAction::Load(slot)) => {
+            load_requests.send(GameLoadRequest { slot });
+            next_state.set(GameState::Playing);
+        }
+        Some(MenuAction::Delete(slot)) => {
+            if let Err(e) = delete_slot(&save_directory.0, &slot) {
+                error!("Failed to delete save slot {slot}: {e:?}");
+            }
+            save_slots.refresh(&save_directory.0);
+            *screen = MenuScreen::LoadGame;
+        }
+        Some(MenuAction::Show(new_screen)) => {
+            *screen = new_screen;
+        }
+        None => {}
+    }
+}
+
+fn show_main_screen(ui: &mut egui::Ui, save_slots: &SaveSlots) -> Option<MenuAction> {
+    let mut action = None;
+    let newest = save_slots.newest();
+    if ui
+        .add_enabled(newest.is_some(), egui::Button::new("Continue"))
+        .clicked()
+    {
+        action = newest.map(|slot| MenuAction::Load(slot.name.clone()));
+    }
+    if ui
+        .add_enabled(!save_slots.0.is_empty(), egui::Button::new("Load Game"))
+        .clicked()
+    {
+        action = Some(MenuAction::Show(MenuScreen::LoadGame));
+    }
+    if ui.button("New Game").clicked() {
+        action = Some(MenuAction::NewGame);
+    }
+    action
+}
+
+fn show_load_game_screen(ui: &mut egui::Ui, save_slots: &SaveSlots) -> Option<MenuAction> {
+    let mut action = None;
+    egui::ScrollArea::vertical()
+        .max_height(ui.available_height() - 60.)
+        .show(ui, |ui| {
+            for slot in save_slots.0.iter() {
+                ui.group(|ui| {
+                    ui.label(&slot.name);
+                    ui.label(format!(
+                        "{} | {} | {}",
+                        format_timestamp(slot.metadata.timestamp),
+                        slot.metadata.level,
+                        format_play_time(slot.metadata.play_time)
+                    ));
+                    ui.label(&slot.metadata.summary);
+                    ui.horizontal(|ui| {
+                        if ui.button("Load").clicked() {
+                            action = Some(MenuAction::Load(slot.name.clone()));
+                        }
+                        if ui.button("Delete").clicked() {
+                            action = Some(MenuAction::Show(MenuScreen::ConfirmDelete(
+                                slot.name.clone(),
+                            )));
+                        }
+                    });
+                });
+            }
+        });
+    ui.add_space(20.);
+    if ui.button("Back").clicked() {
+        action = Some(MenuAction::Show(MenuScreen::Main));
+    }
+    action
+}
+
+fn show_confirm_delete_screen(ui: &mut egui::Ui, slot: &str) -> Option<MenuAction> {
+    let mut action = None;
+    ui.label(format!("Delete save \"{slot}\"? This cannot be undone."));
+    ui.add_space(20.);
+    if ui.button("Delete").clicked() {
+        action = Some(MenuAction::Delete(slot.to_string()));
+    }
+    if ui.button("Cancel").clicked() {
+        action = Some(MenuAction::Show(MenuScreen::LoadGame));
+    }
+    action
 }
 
 fn get_menu_panel() -> egui::CentralPanel {
@@ -1,9 +1,11 @@
 use bevy::prelude::*;
 
+pub(crate) mod behavior;
 pub(crate) mod character_controller;
 
 mod navigation;
 pub(crate) mod physics;
+pub(crate) mod platform;
 
 /// This plugin handles all physical movement that is not exclusive to the player.
 /// It is further split into the following sub-plugins:
@@ -11,11 +13,15 @@ pub(crate) mod physics;
 /// - [`character_controller::plugin`]: Handles kinematic character controller movement. A "character" in
 ///     this sense is anything that behaves in a not-quite completely physical way, like a player, an npc, an elevator, a moving platform, etc.
 ///     Contrast this with pure rigidbodies like a ball, a crate, etc.
+/// - [`behavior::plugin`]: Decides where npcs want to go, e.g. patrolling, following or fleeing.
 /// - [`navigation::plugin`]: Handles npc pathfinding via bevy_pathmesh integration.
+/// - [`platform::plugin`]: Moves platforms and elevators along their waypoints, carrying the characters standing on them.
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         physics::plugin,
         character_controller::plugin,
+        behavior::plugin,
         navigation::plugin,
+        platform::plugin,
     ));
 }
@@ -0,0 +1,319 @@
+use crate::{
+    level_instantiation::on_spawn::{Npc, Player},
+    movement::{character_controller::Sprinting, navigation::NavTarget},
+    util::Vec3Ext,
+    GameSystemSet,
+};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Horizontal distance at which a waypoint or destination counts as reached
+const ARRIVE_DISTANCE: f32 = 0.5;
+/// Seconds after which a wandering NPC gives up on a spot it can't reach and picks another one
+const WANDER_TIMEOUT: f32 = 10.;
+
+/// Decides where NPCs want to go, based on their [`Behavior`]. The navigation then walks them there along the navmesh.
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Behavior>()
+        .register_type::<BehaviorTarget>()
+        .register_type::<PatrolWaypoint>()
+        .register_type::<BehaviorState>()
+        .add_systems(
+            Update,
+            (init_behavior, update_behavior)
+                .chain()
+                .in_set(GameSystemSet::NpcBehavior),
+        );
+}
+
+/// What an NPC does. Can be added in Blender. NPCs without one follow the player.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+pub(crate) enum Behavior {
+    /// Stands still
+    Idle,
+    /// Walks along the [`PatrolWaypoint`]s of a route in order of their index, then starts over
+    Patrol {
+        route: String,
+        /// Seconds spent standing at each waypoint
+        wait: f32,
+    },
+    /// Walks towards the target until it is closer than `distance`
+    Follow {
+        target: BehaviorTarget,
+        distance: f32,
+    },
+    /// Sprints away from the target while it is closer than `safe_distance`
+    Flee {
+        target: BehaviorTarget,
+        safe_distance: f32,
+    },
+    /// Walks to a position and stays there
+    GoTo { position: Vec3 },
+    /// Walks to spots within `radius` of where the NPC spawned, pausing for `pause` seconds at each
+    Wander { radius: f32, pause: f32 },
+}
+
+impl Default for Behavior {
+    fn default() -> Self {
+        Self::Follow {
+            target: default(),
+            distance: 3.,
+        }
+    }
+}
+
+/// An entity a [`Behavior`] reacts to.
+#[derive(Debug, Clone, PartialEq, Eq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize, Default)]
+pub(crate) enum BehaviorTarget {
+    #[default]
+    Player,
+    /// The entity with this [`Name`]
+    Named(String),
+}
+
+/// A point of a patrol route. Can be added to empties in Blender.
+#[derive(Debug, Clone, PartialEq, Eq, Component, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct PatrolWaypoint {
+    #[reflect(default)]
+    pub(crate) route: String,
+    /// Position of the waypoint on its route
+    #[reflect(default)]
+    pub(crate) index: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Default)]
+#[reflect(Component)]
+struct BehaviorState {
+    /// Where the NPC spawned
+    home: Vec3,
+    /// Index into the waypoints of the patrol route, sorted by their own index
+    waypoint: usize,
+    wait_remaining: f32,
+    wander_target: Option<Vec3>,
+    wander_time_remaining: f32,
+    /// Number of wander targets picked so far, seeds the next one
+    wanders: u32,
+}
+
+fn init_behavior(
+    npcs: Query<(Entity, &Transform, Has<Behavior>), Added<Npc>>,
+    mut commands: Commands,
+) {
+    for (entity, transform, has_behavior) in npcs.iter() {
+        let mut npc = commands.entity(entity);
+        npc.insert((
+            BehaviorState {
+                home: transform.translation,
+                ..default()
+            },
+            NavTarget::default(),
+        ));
+        if !has_behavior {
+            npc.insert(Behavior::default());
+        }
+    }
+}
+
+fn update_behavior(
+    time: Res<Time>,
+    mut npcs: Query<(
+        Entity,
+        Ref<Behavior>,
+        &mut BehaviorState,
+        &mut NavTarget,
+        &Transform,
+        Option<&mut Sprinting>,
+    )>,
+    players: Query<&GlobalTransform, With<Player>>,
+    named: Query<(&Name, &GlobalTransform)>,
+    waypoints: Query<(&PatrolWaypoint, &GlobalTransform)>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("update_behavior").entered();
+    let dt = time.delta_seconds();
+    let find_target = |target: &BehaviorTarget| match target {
+        BehaviorTarget::Player => players.iter().next().map(|t| t.translation()),
+        BehaviorTarget::Named(name) => named
+            .iter()
+            .find(|(entity_name, _)| entity_name.as_str() == name)
+            .map(|(_, t)| t.translation()),
+    };
+    for (entity, behavior, mut state, mut nav_target, transform, sprinting) in &mut npcs {
+        if behavior.is_changed() {
+            *state = BehaviorState {
+                home: state.home,
+                ..default()
+            };
+        }
+        let position = transform.translation;
+        let reached = |goal: Vec3| (goal - position).horizontal().length() < ARRIVE_DISTANCE;
+        let mut sprint = false;
+        let target = match behavior.as_ref() {
+            Behavior::Idle => NavTarget::default(),
+            Behavior::Patrol { route, wait } => {
+                let mut route_points: Vec<_> = waypoints
+                    .iter()
+                    .filter(|(waypoint, _)| &waypoint.route == route)
+                    .map(|(waypoint, transform)| (waypoint.index, transform.translation()))
+                    .collect();
+                route_points.sort_by_key(|(index, _)| *index);
+                if route_points.is_empty() {
+                    NavTarget::default()
+                } else {
+                    state.waypoint %= route_points.len();
+                    let (_, waypoint) = route_points[state.waypoint];
+                    if state.wait_remaining > 0. {
+                        state.wait_remaining -= dt;
+                        NavTarget::default()
+                    } else if reached(waypoint) {
+                        state.waypoint = (state.waypoint + 1) % route_points.len();
+                        state.wait_remaining = *wait;
+                        NavTarget::default()
+                    } else {
+                        NavTarget::at(waypoint)
+                    }
+                }
+            }
+            Behavior::Follow { target, distance } => NavTarget {
+                position: find_target(target),
+                stop_distance: *distance,
+            },
+            Behavior::Flee {
+                target,
+                safe_distance,
+            } => match find_target(target) {
+                Some(threat) if position.distance(threat) < *safe_distance => {
+                    let away = (position - threat)
+                        .horizontal()
+                        .try_normalize()
+                        .unwrap_or(Vec3::X);
+                    sprint = true;
+                    NavTarget::at(position + away * *safe_distance)
+                }
+                _ => NavTarget::default(),
+            },
+            Behavior::GoTo { position: goal } => {
+                if reached(*goal) {
+                    NavTarget::default()
+                } else {
+                    NavTarget::at(*goal)
+                }
+            }
+            Behavior::Wander { radius, pause } => {
+                if state.wait_remaining > 0. {
+                    state.wait_remaining -= dt;
+                    NavTarget::default()
+                } else {
+                    let goal = match state.wander_target {
+                        Some(goal) => goal,
+                        None => {
+                            let seed = entity.index().wrapping_mul(31).wrapping_add(state.wanders);
+                            let goal = wander_point(state.home, *radius, seed);
+                            state.wanders += 1;
+                            state.wander_target = Some(goal);
+                            state.wander_time_remaining = WANDER_TIMEOUT;
+                            goal
+                        }
+                    };
+                    state.wander_time_remaining -= dt;
+                    if reached(goal) || state.wander_time_remaining <= 0. {
+                        state.wander_target = None;
+                        state.wait_remaining = *pause;
+                        NavTarget::default()
+                    } else {
+                        NavTarget::at(goal)
+                    }
+                }
+            }
+        };
+        if *nav_target != target {
+            *nav_target = target;
+        }
+        if let Some(mut sprinting) = sprinting {
+            if sprinting.requested != sprint {
+                sprinting.requested = sprint;
+            }
+        }
+    }
+}
+
+/// Deterministic, so that replays see the same wandering.
+/// Successive seeds are spread around the home by the golden angle.
+fn wander_point(home: Vec3, radius: f32, seed: u32) -> Vec3 {
+    const GOLDEN_ANGLE: f32 = 2.399_963;
+    const GOLDEN_RATIO_FRACTION: f32 = 0.618_034;
+    let angle = seed as f32 * GOLDEN_ANGLE;
+    // The square root spreads the points evenly over the area of the circle
+    let distance = radius * (seed as f32 * GOLDEN_RATIO_FRACTION).fract().sqrt();
+    home + Vec3::new(angle.cos(), 0., angle.sin()) * distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TestApp, TestEntity};
+
+    #[test]
+    fn npc_patrols_waypoints_in_order() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(30.));
+        for (index, translation) in [Vec3::new(8., 0., 0.), Vec3::new(8., 0., 8.)]
+            .into_iter()
+            .enumerate()
+        {
+            app.app.world.spawn((
+                PatrolWaypoint {
+                    route: "Guard".to_string(),
+                    index: index as u32,
+                },
+                TransformBundle::from_transform(Transform::from_translation(translation)),
+            ));
+        }
+        let npc = app.spawn(
+            TestEntity::npc()
+                .at(Vec3::new(0., 1., 0.))
+                .with(Behavior::Patrol {
+                    route: "Guard".to_string(),
+                    wait: 0.,
+                }),
+        );
+
+        let near = |app: &TestApp, point: Vec3| {
+            (app.translation(npc) - point).horizontal().length() < ARRIVE_DISTANCE * 2.
+        };
+        let first = app.tick_until(64 * 20, |app| near(app, Vec3::new(8., 0., 0.)));
+        assert!(first.is_some(), "NPC never reached the first waypoint");
+        let second = app.tick_until(64 * 20, |app| near(app, Vec3::new(8., 0., 8.)));
+        assert!(second.is_some(), "NPC never reached the second waypoint");
+    }
+
+    #[test]
+    fn npc_flees_from_player() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(30.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        let npc = app.spawn(
+            TestEntity::npc()
+                .at(Vec3::new(2., 1., 0.))
+                .with(Behavior::Flee {
+                    target: BehaviorTarget::Player,
+                    safe_distance: 8.,
+                }),
+        );
+
+        let escaped = app.tick_until(64 * 20, |app| {
+            app.translation(npc).distance(app.translation(player)) > 7.
+        });
+
+        assert!(
+            escaped.is_some(),
+            "NPC at {} never got away from the player at {}",
+            app.translation(npc),
+            app.translation(player)
+        );
+    }
+}
@@ -1,28 +1,65 @@
+use crate::file_system_interaction::config::{GameConfig, MovementTuning};
+use crate::level_instantiation::on_spawn::Player;
+use crate::physics_time::{PhysicsSchedule, PhysicsTime};
 use crate::system_set::GameSystemSet;
 pub(crate) use animation::AnimationState;
 use bevy::prelude::*;
-use bevy_tnua::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::{
+    builtins::{TnuaBuiltinCrouch, TnuaBuiltinDash},
+    control_helpers::{TnuaCrouchEnforcer, TnuaCrouchEnforcerPlugin, TnuaSimpleAirActionsCounter},
+    prelude::*,
+    TnuaAction, TnuaProximitySensor,
+};
 use bevy_tnua_rapier3d::*;
+pub(crate) use climbing::{ClimbBasis, Climbable};
 pub(crate) use components::*;
+pub(crate) use swimming::{SplashEvent, WaterVolume};
 
 mod animation;
+mod climbing;
 mod components;
 mod models;
+mod swimming;
 
 /// This plugin communicates with the Tnua character controller by propagating settings found in
-/// the control components [`Walk`] and [`Jump`]. It also controls a state machine to determine which animations to play.
+/// the control components [`Walk`], [`Jump`], [`Crouch`], [`Dash`] and [`SlopeSlide`]. Characters with a [`Push`] push dynamic rigid bodies they walk into
+/// and characters with a [`Swim`] swim in [`WaterVolume`]s. Characters with a [`Climb`] climb [`Climbable`]s. It also controls a state machine to determine which animations to play.
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((components::plugin, animation::plugin, models::plugin))
-        .add_plugins((
-            TnuaRapier3dPlugin::default(),
-            TnuaControllerPlugin::default(),
-        ))
-        .add_systems(
-            Update,
-            (apply_jumping, apply_walking)
-                .chain()
-                .in_set(GameSystemSet::GeneralMovement),
-        );
+    app.add_plugins((
+        components::plugin,
+        animation::plugin,
+        climbing::plugin,
+        models::plugin,
+        swimming::plugin,
+    ))
+    .add_plugins((
+        TnuaRapier3dPlugin::default(),
+        TnuaControllerPlugin::default(),
+        TnuaCrouchEnforcerPlugin::default(),
+    ))
+    .add_systems(
+        Update,
+        (
+            apply_movement_config,
+            swimming::read_swim_input,
+            climbing::apply_climbing,
+            apply_crouching,
+            gate_with_stamina,
+            apply_jumping,
+            apply_dashing,
+            detect_slope_sliding,
+            apply_walking,
+            spend_stamina,
+            resize_crouching_colliders,
+        )
+            .chain()
+            .in_set(GameSystemSet::GeneralMovement),
+    )
+    .add_systems(
+        PhysicsSchedule,
+        apply_pushing.before(PhysicsSet::SyncBackend),
+    );
 }
 
 fn apply_walking(
@@ -30,40 +67,660 @@ fn apply_walking(
         &mut TnuaController,
         &mut Walk,
         Option<&Sprinting>,
+        Option<&Crouch>,
+        Option<&SlopeSlide>,
+        Option<&Jump>,
+        Option<&Swim>,
+        Option<&Climb>,
         &FloatHeight,
     )>,
 ) {
     #[cfg(feature = "tracing")]
     let _span = info_span!("apply_walking").entered();
-    for (mut controller, mut walking, sprinting, float_height) in &mut character_query {
+    for (
+        mut controller,
+        mut walking,
+        sprinting,
+        crouch,
+        slope_slide,
+        jump,
+        swim,
+        climb,
+        float_height,
+    ) in &mut character_query
+    {
+        if climb.is_some_and(|c| c.climbing) {
+            // Already got a climb basis this frame
+            continue;
+        }
         let direction = walking.direction.unwrap_or_default();
         let sprinting_multiplier = sprinting
             .filter(|s| s.requested)
             .map(|s| s.multiplier)
             .unwrap_or(1.);
-        let speed = walking.speed * sprinting_multiplier;
+        let crouching_multiplier = crouch
+            .filter(|_| controller.action_name() == Some(TnuaBuiltinCrouch::NAME))
+            .map(|c| c.speed_multiplier)
+            .unwrap_or(1.);
+        let swimming_multiplier = swim
+            .filter(|s| s.swimming)
+            .map(|s| s.speed_multiplier)
+            .unwrap_or(1.);
+        let speed =
+            walking.speed * sprinting_multiplier * crouching_multiplier * swimming_multiplier;
+        let default_walk = TnuaBuiltinWalk::default();
         controller.basis(TnuaBuiltinWalk {
             desired_velocity: direction * speed,
             desired_forward: direction.normalize_or_zero(),
             float_height: float_height.0,
-            cling_distance: 0.1,
-            ..Default::default()
+            acceleration: walking.acceleration,
+            air_acceleration: walking.air_acceleration,
+            turning_angvel: walking.turning_speed,
+            cling_distance: walking.cling_distance,
+            max_slope: slope_slide.map_or(default_walk.max_slope, |s| s.max_slope),
+            coyote_time: jump.map_or(default_walk.coyote_time, |j| j.coyote_time),
+            ..default_walk
         });
         walking.direction = None;
     }
 }
 
-fn apply_jumping(mut character_query: Query<(&mut TnuaController, &mut Jump)>) {
+/// Jumps are fed to Tnua for as long as they are requested, which is what makes releasing the button early jump lower.
+fn apply_jumping(
+    mut character_query: Query<(
+        &mut TnuaController,
+        &mut TnuaSimpleAirActionsCounter,
+        &mut Jump,
+        Option<&Dash>,
+    )>,
+) {
     #[cfg(feature = "tracing")]
     let _span = info_span!("apply_jumping").entered();
-    for (mut controller, mut jump) in &mut character_query {
+    for (mut controller, mut air_actions_counter, mut jump, dash) in &mut character_query {
+        // Needs to see the controller every frame to count the actions started in the air
+        air_actions_counter.update(controller.as_mut());
         if jump.requested {
+            // Tnua counts air dashes as air actions too, but they have their own budget in the `Dash`
+            let air_dashes = dash.map_or(0, |dash| dash.air_dashes_used as usize);
+            let air_jumps = air_actions_counter
+                .air_count_for(TnuaBuiltinJump::NAME)
+                .saturating_sub(air_dashes);
             controller.action(TnuaBuiltinJump {
                 height: jump.height,
-                takeoff_extra_gravity: 10.0,
+                allow_in_air: air_jumps <= jump.max_air_jumps as usize,
+                takeoff_extra_gravity: jump.takeoff_extra_gravity,
+                shorten_extra_gravity: jump.release_extra_gravity,
+                fall_extra_gravity: jump.fall_extra_gravity,
+                input_buffer_time: jump.buffer_time,
                 ..Default::default()
             });
             jump.requested = false;
         }
     }
 }
+
+/// Applies the movement tuning of the config to new characters and to all characters when the config changes.
+/// The player uses the `player` archetype, all other characters the `npc` one.
+fn apply_movement_config(
+    config: Option<Res<GameConfig>>,
+    mut characters: Query<(
+        &mut Walk,
+        &mut Jump,
+        Option<&mut Sprinting>,
+        Option<&mut SlopeSlide>,
+        Has<Player>,
+    )>,
+) {
+    let Some(config) = config else {
+        return;
+    };
+    for (mut walk, mut jump, sprinting, slope_slide, is_player) in &mut characters {
+        if !config.is_changed() && !walk.is_added() {
+            continue;
+        }
+        let tuning: &MovementTuning = if is_player {
+            &config.movement.player
+        } else {
+            &config.movement.npc
+        };
+        walk.speed = tuning.speed;
+        walk.acceleration = tuning.acceleration;
+        walk.air_acceleration = tuning.air_acceleration;
+        walk.turning_speed = tuning.turning_speed;
+        walk.cling_distance = tuning.cling_distance;
+        walk.backwards_modifier = tuning.backwards_modifier;
+        if let Some(mut sprinting) = sprinting {
+            sprinting.multiplier = tuning.sprint_multiplier;
+        }
+        if let Some(mut slope_slide) = slope_slide {
+            slope_slide.max_slope = tuning.max_slope_degrees.to_radians();
+        }
+        let jump_tuning = &tuning.jump;
+        jump.height = jump_tuning.height;
+        jump.coyote_time = jump_tuning.coyote_time;
+        jump.buffer_time = jump_tuning.buffer_time;
+        jump.max_air_jumps = jump_tuning.max_air_jumps;
+        jump.takeoff_extra_gravity = jump_tuning.takeoff_extra_gravity;
+        jump.release_extra_gravity = jump_tuning.release_extra_gravity;
+        jump.fall_extra_gravity = jump_tuning.fall_extra_gravity;
+    }
+}
+
+/// Drops sprint and jump requests the character can't afford. Jumps that already started are not interrupted.
+fn gate_with_stamina(
+    mut character_query: Query<(&TnuaController, &Stamina, &mut Jump, Option<&mut Sprinting>)>,
+) {
+    for (controller, stamina, mut jump, sprinting) in &mut character_query {
+        if let Some(mut sprinting) = sprinting {
+            if sprinting.requested && !stamina.can_afford(0.) {
+                sprinting.requested = false;
+            }
+        }
+        let is_jumping = controller.action_name() == Some(TnuaBuiltinJump::NAME);
+        if jump.requested && !is_jumping && !stamina.can_afford(stamina.jump_cost) {
+            jump.requested = false;
+        }
+    }
+}
+
+fn spend_stamina(
+    time: Res<Time>,
+    mut character_query: Query<(Entity, &TnuaController, &mut Stamina, Option<&Sprinting>)>,
+    mut stamina_events: EventWriter<StaminaEvent>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, controller, mut stamina, sprinting) in &mut character_query {
+        let is_moving = controller
+            .concrete_basis::<TnuaBuiltinWalk>()
+            .is_some_and(|(walk, _)| walk.desired_velocity.length_squared() > 0.01);
+        let mut cost = 0.;
+        if sprinting.is_some_and(|s| s.requested) && is_moving {
+            cost += stamina.sprint_cost_per_second * dt;
+        }
+        if controller.action_flow_status().just_starting() == Some(TnuaBuiltinJump::NAME) {
+            cost += stamina.jump_cost;
+        }
+
+        if cost > 0. {
+            stamina.current = (stamina.current - cost).max(0.);
+            stamina.regen_delay_remaining = stamina.regen_delay;
+            if stamina.current <= 0. && !stamina.exhausted {
+                stamina.exhausted = true;
+                stamina_events.send(StaminaEvent::Exhausted(entity));
+            }
+        } else if stamina.regen_delay_remaining > 0. {
+            stamina.regen_delay_remaining -= dt;
+        } else if stamina.current < stamina.max {
+            stamina.current = (stamina.current + stamina.regen_rate * dt).min(stamina.max);
+            if stamina.current >= stamina.max && stamina.exhausted {
+                stamina.exhausted = false;
+                stamina_events.send(StaminaEvent::Recovered(entity));
+            }
+        }
+    }
+}
+
+/// Crouching is an action that has to be fed every frame for as long as the character crouches.
+fn apply_crouching(
+    mut character_query: Query<(&mut TnuaController, &mut TnuaCrouchEnforcer, &Crouch)>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("apply_crouching").entered();
+    for (mut controller, mut crouch_enforcer, crouch) in &mut character_query {
+        if crouch.requested {
+            controller.action(crouch_enforcer.enforcing(TnuaBuiltinCrouch {
+                float_offset: crouch.float_offset,
+                ..Default::default()
+            }));
+        }
+    }
+}
+
+fn apply_dashing(
+    time: Res<Time>,
+    mut character_query: Query<(&mut TnuaController, &mut Dash, &Walk, &Transform)>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("apply_dashing").entered();
+    for (mut controller, mut dash, walk, transform) in &mut character_query {
+        dash.cooldown_remaining = (dash.cooldown_remaining - time.delta_seconds()).max(0.);
+        let is_airborne = controller.is_airborne().unwrap_or_default();
+        if !is_airborne {
+            dash.air_dashes_used = 0;
+        }
+        if !std::mem::take(&mut dash.requested) || dash.cooldown_remaining > 0. {
+            continue;
+        }
+        if is_airborne {
+            if dash.air_dashes_used >= dash.max_air_dashes {
+                continue;
+            }
+            dash.air_dashes_used += 1;
+        }
+        let direction = walk
+            .direction
+            .map(|direction| Vec3::new(direction.x, 0., direction.z).normalize_or_zero())
+            .filter(|direction| *direction != Vec3::ZERO)
+            .unwrap_or_else(|| transform.forward().into());
+        controller.action(TnuaBuiltinDash {
+            displacement: direction * dash.distance,
+            desired_forward: direction,
+            allow_in_air: true,
+            speed: dash.speed,
+            ..Default::default()
+        });
+        dash.cooldown_remaining = dash.cooldown;
+    }
+}
+
+fn detect_slope_sliding(mut character_query: Query<(&mut SlopeSlide, &TnuaProximitySensor)>) {
+    for (mut slope_slide, sensor) in &mut character_query {
+        let sliding = sensor
+            .output
+            .as_ref()
+            .is_some_and(|output| output.normal.angle_between(Vec3::Y) > slope_slide.max_slope);
+        if slope_slide.sliding != sliding {
+            slope_slide.sliding = sliding;
+        }
+    }
+}
+
+/// Shrinks the collider of crouching characters so they fit through low gaps.
+fn resize_crouching_colliders(
+    mut commands: Commands,
+    character_query: Query<(Entity, &TnuaController, &Crouch, Option<&ColliderScale>)>,
+) {
+    for (entity, controller, crouch, scale) in &character_query {
+        let is_crouching = controller.action_name() == Some(TnuaBuiltinCrouch::NAME);
+        let target = if is_crouching {
+            Vec3::new(1., crouch.collider_scale, 1.)
+        } else {
+            Vec3::ONE
+        };
+        let current = match scale {
+            Some(ColliderScale::Relative(scale)) => *scale,
+            _ => Vec3::ONE,
+        };
+        if current != target {
+            commands
+                .entity(entity)
+                .insert(ColliderScale::Relative(target));
+        }
+    }
+}
+
+/// Tnua keeps characters upright with strong forces, so plain contacts barely move props.
+/// Instead, every tick each touched dynamic body gets an impulse along the character's horizontal movement towards it.
+fn apply_pushing(
+    time: Res<PhysicsTime>,
+    characters: Query<(&Push, &Velocity, &GlobalTransform, &CollidingEntities)>,
+    parents: Query<&Parent>,
+    mut bodies: Query<
+        (
+            &RigidBody,
+            &GlobalTransform,
+            &mut ExternalImpulse,
+            Option<&mut Sleeping>,
+        ),
+        Without<TnuaController>,
+    >,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("apply_pushing").entered();
+    let dt = time.context().timestep.as_secs_f32();
+    for (push, velocity, transform, colliding_entities) in characters.iter() {
+        let horizontal_velocity = Vec3::new(velocity.linvel.x, 0., velocity.linvel.z);
+        if horizontal_velocity.length_squared() < 1e-4 {
+            continue;
+        }
+        for collider in colliding_entities.iter() {
+            // Colliders from Blender are children of the rigid body
+            let Some(body) = std::iter::once(collider)
+                .chain(parents.iter_ancestors(collider))
+                .find(|&entity| bodies.contains(entity))
+            else {
+                continue;
+            };
+            let Ok((rigid_body, body_transform, mut impulse, sleeping)) = bodies.get_mut(body)
+            else {
+                continue;
+            };
+            if *rigid_body != RigidBody::Dynamic {
+                continue;
+            }
+            let offset = body_transform.translation() - transform.translation();
+            let direction = Vec3::new(offset.x, 0., offset.z).normalize_or_zero();
+            let speed_towards_body = horizontal_velocity.dot(direction);
+            if speed_towards_body <= 0. {
+                continue;
+            }
+            let force = (speed_towards_body * push.force_per_speed).min(push.max_force);
+            impulse.impulse += horizontal_velocity.normalize() * force * dt;
+            if let Some(mut sleeping) = sleeping {
+                sleeping.sleeping = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Crouch, Dash, Jump, Sprinting, Stamina, Walk};
+    use crate::file_system_interaction::config::GameConfig;
+    use crate::level_instantiation::on_spawn::player;
+    use crate::physics_time::DEFAULT_TIMESTEP;
+    use crate::test_harness::{TestApp, TestEntity};
+    use crate::util::Vec3Ext;
+    use bevy::prelude::*;
+    use bevy_rapier3d::prelude::ColliderScale;
+    use bevy_tnua::{builtins::TnuaBuiltinDash, controller::TnuaController, TnuaAction};
+
+    /// Ticks needed for a freshly spawned player to settle on the ground
+    const SETTLE_TICKS: u32 = 120;
+
+    #[test]
+    fn player_lands_on_ground() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 3., 0.)));
+
+        app.ticks(SETTLE_TICKS);
+
+        let translation = app.translation(player);
+        assert!(
+            translation.y > 0. && translation.y < 1.,
+            "Player should stand on the ground, but is at {translation}"
+        );
+    }
+
+    #[test]
+    fn player_walks_away_from_camera() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(SETTLE_TICKS);
+        let start = app.translation(player);
+
+        app.press(KeyCode::KeyW);
+        app.ticks(64);
+
+        let end = app.translation(player);
+        assert!(
+            start.z - end.z > 2.,
+            "Player should walk along -Z, but went from {start} to {end}"
+        );
+
+        app.release(KeyCode::KeyW);
+        app.ticks(32);
+        let stop = app.translation(player);
+        app.ticks(32);
+        let stopped = app.translation(player);
+        assert!(
+            stop.distance(stopped) < 0.1,
+            "Player should stop walking, but went from {stop} to {stopped}"
+        );
+    }
+
+    #[test]
+    fn player_jumps() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(SETTLE_TICKS);
+        let start = app.translation(player);
+
+        app.press(KeyCode::Space);
+        let jumped = app.tick_until(64, |app| app.translation(player).y > start.y + 0.5);
+
+        assert!(jumped.is_some(), "Player did not jump from {start}");
+    }
+
+    #[test]
+    fn releasing_jump_early_jumps_lower() {
+        let peak_height = |held_ticks: u32| {
+            let mut app = TestApp::new();
+            app.spawn(TestEntity::ground(20.));
+            let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+            app.ticks(SETTLE_TICKS);
+            let start = app.translation(player).y;
+
+            app.press(KeyCode::Space);
+            app.ticks(held_ticks);
+            app.release(KeyCode::Space);
+            let mut peak = start;
+            for _ in 0..64 {
+                app.tick();
+                peak = peak.max(app.translation(player).y);
+            }
+            peak - start
+        };
+
+        let tapped = peak_height(3);
+        let held = peak_height(64);
+        assert!(
+            tapped < held,
+            "Tapping jump should jump lower than holding it, but reached {tapped} and {held}"
+        );
+    }
+
+    #[test]
+    fn exhausted_player_can_neither_sprint_nor_jump() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(SETTLE_TICKS);
+        let start = app.translation(player);
+        app.app.world.get_mut::<Stamina>(player).unwrap().current = 1.;
+
+        app.press(KeyCode::KeyW);
+        app.press(KeyCode::ShiftLeft);
+        app.ticks(16);
+
+        let stamina = app.app.world.get::<Stamina>(player).unwrap();
+        assert!(stamina.exhausted, "Sprinting should exhaust the player");
+        assert!(!app.app.world.get::<Sprinting>(player).unwrap().requested);
+
+        app.release(KeyCode::KeyW);
+        app.release(KeyCode::ShiftLeft);
+        app.press(KeyCode::Space);
+        app.ticks(32);
+        let end = app.translation(player);
+        assert!(
+            end.y < start.y + 0.2,
+            "Exhausted player should not jump, but went from {start} to {end}"
+        );
+    }
+
+    #[test]
+    fn player_crouches_and_stands_up() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(SETTLE_TICKS);
+        let standing = app.translation(player);
+        let collider_scale = |app: &TestApp| match app.app.world.get::<ColliderScale>(player) {
+            Some(ColliderScale::Relative(scale)) => scale.y,
+            _ => 1.,
+        };
+
+        app.press(KeyCode::ControlLeft);
+        app.ticks(32);
+
+        let crouching = app.translation(player);
+        let crouch = app.app.world.get::<Crouch>(player).unwrap().clone();
+        // Tnua lowers the float height, but the shrunk collider resting on the ground may keep the player higher
+        let expected =
+            (standing.y + crouch.float_offset).max(player::RADIUS * crouch.collider_scale);
+        assert!(
+            (crouching.y - expected).abs() < 0.02,
+            "Player should crouch at a height of {expected}, but went from {standing} to {crouching}"
+        );
+        assert_eq!(collider_scale(&app), crouch.collider_scale);
+
+        app.release(KeyCode::ControlLeft);
+        app.ticks(32);
+        let stood_up = app.translation(player);
+        assert_eq!(collider_scale(&app), 1.);
+        assert!(
+            (stood_up.y - standing.y).abs() < 0.02,
+            "Player should stand up to {standing}, but is at {stood_up}"
+        );
+    }
+
+    #[test]
+    fn player_dashes_forward() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(SETTLE_TICKS);
+        let start = app.translation(player);
+        let dash = app.app.world.get::<Dash>(player).unwrap().clone();
+        let dashing = |app: &TestApp| {
+            app.app
+                .world
+                .get::<TnuaController>(player)
+                .unwrap()
+                .action_name()
+                == Some(TnuaBuiltinDash::NAME)
+        };
+
+        app.press(KeyCode::AltLeft);
+        let started = app.tick_until(8, |app| dashing(app));
+        assert!(started.is_some(), "Player did not start dashing");
+        app.release(KeyCode::AltLeft);
+        let finished = app.tick_until(64, |app| !dashing(app));
+        assert!(finished.is_some(), "Player did not finish dashing");
+
+        let end = app.translation(player);
+        let distance = (end - start).horizontal().length();
+        // The dash ends on the tick it reaches its destination
+        let tolerance = dash.speed * DEFAULT_TIMESTEP.as_secs_f32();
+        assert!(
+            (distance - dash.distance).abs() <= tolerance,
+            "Player should dash {} m, but went from {start} to {end}",
+            dash.distance
+        );
+        assert!(
+            start.z - end.z > distance * 0.9,
+            "Player should dash forward along -Z, but went from {start} to {end}"
+        );
+    }
+
+    #[test]
+    fn air_dash_does_not_use_up_air_jump() {
+        let mut app = TestApp::with_config();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(SETTLE_TICKS);
+        let start = app.translation(player);
+        assert_eq!(app.app.world.get::<Jump>(player).unwrap().max_air_jumps, 1);
+        let dashing = |app: &TestApp| {
+            app.app
+                .world
+                .get::<TnuaController>(player)
+                .unwrap()
+                .action_name()
+                == Some(TnuaBuiltinDash::NAME)
+        };
+
+        app.press(KeyCode::Space);
+        let jumped = app.tick_until(64, |app| app.translation(player).y > start.y + 0.5);
+        assert!(jumped.is_some(), "Player did not jump from {start}");
+        app.release(KeyCode::Space);
+        app.tick();
+
+        app.press(KeyCode::AltLeft);
+        let dashed = app.tick_until(8, |app| dashing(app));
+        assert!(dashed.is_some(), "Player did not dash in the air");
+        app.release(KeyCode::AltLeft);
+        let finished = app.tick_until(64, |app| !dashing(app));
+        assert!(finished.is_some(), "Player did not finish dashing");
+        assert_eq!(
+            app.app.world.get::<Dash>(player).unwrap().air_dashes_used,
+            1
+        );
+
+        let before_air_jump = app.translation(player);
+        app.press(KeyCode::Space);
+        let air_jumped = app.tick_until(32, |app| {
+            app.translation(player).y > before_air_jump.y + 0.3
+        });
+        assert!(
+            air_jumped.is_some(),
+            "Player should still have an air jump after an air dash, but fell from {before_air_jump} to {}",
+            app.translation(player)
+        );
+    }
+
+    #[test]
+    fn sprinting_drains_stamina_per_second() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(SETTLE_TICKS);
+        let stamina = app.app.world.get::<Stamina>(player).unwrap().clone();
+        let ticks_per_second = (1. / DEFAULT_TIMESTEP.as_secs_f32()) as u32;
+
+        app.press(KeyCode::KeyW);
+        app.press(KeyCode::ShiftLeft);
+        app.ticks(ticks_per_second);
+
+        let drained = stamina.current - app.app.world.get::<Stamina>(player).unwrap().current;
+        // Sprinting may only start to cost on the tick after the key was pressed
+        let cost_per_tick = stamina.sprint_cost_per_second * DEFAULT_TIMESTEP.as_secs_f32();
+        assert!(
+            (drained - stamina.sprint_cost_per_second).abs() <= cost_per_tick + 1e-3,
+            "A second of sprinting should cost {}, but cost {drained}",
+            stamina.sprint_cost_per_second
+        );
+    }
+
+    #[test]
+    fn jumping_costs_stamina_once() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(SETTLE_TICKS);
+        let start = app.translation(player);
+        let stamina = app.app.world.get::<Stamina>(player).unwrap().clone();
+
+        app.press(KeyCode::Space);
+        let jumped = app.tick_until(64, |app| app.translation(player).y > start.y + 0.5);
+        assert!(jumped.is_some(), "Player did not jump from {start}");
+        app.ticks(8);
+
+        let current = app.app.world.get::<Stamina>(player).unwrap().current;
+        assert_eq!(current, stamina.current - stamina.jump_cost);
+    }
+
+    #[test]
+    fn movement_config_is_applied_per_archetype() {
+        let mut app = TestApp::with_config();
+        app.spawn(TestEntity::ground(20.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        let npc = app.spawn(TestEntity::npc().at(Vec3::new(3., 1., 0.)));
+        app.tick();
+        let config = app.app.world.resource::<GameConfig>().movement.clone();
+
+        for (character, tuning) in [(player, &config.player), (npc, &config.npc)] {
+            let walk = app.app.world.get::<Walk>(character).unwrap();
+            assert_eq!(walk.speed, tuning.speed);
+            assert_eq!(walk.backwards_modifier, tuning.backwards_modifier);
+            let jump = app.app.world.get::<Jump>(character).unwrap();
+            assert_eq!(jump.max_air_jumps, tuning.jump.max_air_jumps);
+        }
+        assert_ne!(
+            config.player.jump.max_air_jumps, config.npc.jump.max_air_jumps,
+            "The archetypes should differ for this test to be meaningful"
+        );
+
+        app.config_mut().movement.npc.speed = 3.;
+        app.tick();
+
+        assert_eq!(app.app.world.get::<Walk>(npc).unwrap().speed, 3.);
+        assert_eq!(
+            app.app.world.get::<Walk>(player).unwrap().speed,
+            config.player.speed
+        );
+    }
+}
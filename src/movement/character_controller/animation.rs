@@ -1,10 +1,13 @@
+use crate::movement::character_controller::{ClimbBasis, SlopeSlide, Swim};
 use crate::system_set::GameSystemSet;
 use crate::util::error;
+use anyhow::Context;
 use bevy::{animation::AnimationPlayer, prelude::*};
 use bevy_gltf_blueprints::{AnimationPlayerLink, Animations};
 use bevy_tnua::{
-    builtins::TnuaBuiltinWalk, controller::TnuaController, TnuaAnimatingState,
-    TnuaAnimatingStateDirective,
+    builtins::{TnuaBuiltinCrouch, TnuaBuiltinDash, TnuaBuiltinWalk},
+    controller::TnuaController,
+    TnuaAction, TnuaAnimatingState, TnuaAnimatingStateDirective,
 };
 use std::time::Duration;
 
@@ -24,14 +27,32 @@ pub(crate) enum AnimationState {
     Airborne,
     Walking(f32),
     Running(f32),
+    Crouching(f32),
+    Dashing,
+    Sliding,
+    Swimming,
+    /// Vertical speed
+    Climbing(f32),
 }
 
+/// Names of the animations of a character model. Can be added in Blender.
+/// The optional animations fall back to the closest required one if a model doesn't have them.
 #[derive(Debug, Clone, PartialEq, Component, Reflect, Default)]
 #[reflect(Component)]
 struct CharacterAnimationNames {
     idle: String,
     walk: String,
     aerial: String,
+    #[reflect(default)]
+    crouch: Option<String>,
+    #[reflect(default)]
+    dash: Option<String>,
+    #[reflect(default)]
+    slide: Option<String>,
+    #[reflect(default)]
+    swim: Option<String>,
+    #[reflect(default)]
+    climb: Option<String>,
 }
 
 fn play_animations(
@@ -39,6 +60,8 @@ fn play_animations(
         Entity,
         &mut TnuaAnimatingState<AnimationState>,
         &TnuaController,
+        Option<&SlopeSlide>,
+        Option<&Swim>,
         &AnimationPlayerLink,
         &Animations,
     )>,
@@ -48,7 +71,9 @@ fn play_animations(
 ) -> anyhow::Result<()> {
     #[cfg(feature = "tracing")]
     let _span = info_span!("play_animations").entered();
-    for (entity, mut animating_state, controller, link, animations) in query.iter_mut() {
+    for (entity, mut animating_state, controller, slope_slide, swim, link, animations) in
+        query.iter_mut()
+    {
         let Some(animation_names) = children
             .iter_descendants(entity)
             .filter_map(|entity| animation_names.get(entity).ok())
@@ -58,18 +83,31 @@ fn play_animations(
         };
         let mut animation_player = animation_players.get_mut(link.0)?;
         match animating_state.update_by_discriminant({
-            let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
-                continue;
-            };
-            let speed = basis_state.running_velocity.length();
-            if controller.is_airborne()? {
-                AnimationState::Airborne
-            } else if speed > 10.0 {
-                AnimationState::Running(speed)
-            } else if speed > 0.01 {
-                AnimationState::Walking(speed)
+            if let Some((_, climb_state)) = controller.concrete_basis::<ClimbBasis>() {
+                AnimationState::Climbing(climb_state.velocity.y)
             } else {
-                AnimationState::Standing
+                let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+                    continue;
+                };
+                let speed = basis_state.running_velocity.length();
+                let action = controller.action_name();
+                if action == Some(TnuaBuiltinDash::NAME) {
+                    AnimationState::Dashing
+                } else if swim.is_some_and(|s| s.swimming) {
+                    AnimationState::Swimming
+                } else if slope_slide.is_some_and(|s| s.sliding) {
+                    AnimationState::Sliding
+                } else if controller.is_airborne()? {
+                    AnimationState::Airborne
+                } else if action == Some(TnuaBuiltinCrouch::NAME) {
+                    AnimationState::Crouching(speed)
+                } else if speed > 10.0 {
+                    AnimationState::Running(speed)
+                } else if speed > 0.01 {
+                    AnimationState::Walking(speed)
+                } else {
+                    AnimationState::Standing
+                }
             }
         }) {
             TnuaAnimatingStateDirective::Maintain { state } => {
@@ -83,44 +121,61 @@ fn play_animations(
                 // animations.
                 old_state: _,
                 state,
-            } => match state {
-                AnimationState::Airborne | AnimationState::Running(..) => {
-                    animation_player
-                        .play_with_transition(
-                            animations
-                                .named_animations
-                                .get(&animation_names.aerial)
-                                .unwrap()
-                                .clone_weak(),
-                            Duration::from_secs_f32(0.2),
-                        )
-                        .repeat();
-                }
-                AnimationState::Standing => {
-                    animation_player
-                        .play_with_transition(
-                            animations
-                                .named_animations
-                                .get(&animation_names.idle)
-                                .unwrap()
-                                .clone_weak(),
-                            Duration::from_secs_f32(0.2),
-                        )
-                        .repeat();
-                }
-                AnimationState::Walking(_speed) => {
-                    animation_player
-                        .play_with_transition(
-                            animations
-                                .named_animations
-                                .get(&animation_names.walk)
-                                .unwrap()
-                                .clone_weak(),
-                            Duration::from_secs_f32(0.1),
-                        )
-                        .repeat();
-                }
-            },
+            } => {
+                let (name, transition) = match state {
+                    AnimationState::Airborne | AnimationState::Running(..) => {
+                        (&animation_names.aerial, 0.2)
+                    }
+                    AnimationState::Standing => (&animation_names.idle, 0.2),
+                    AnimationState::Walking(_speed) => (&animation_names.walk, 0.1),
+                    AnimationState::Crouching(speed) => (
+                        animation_names.crouch.as_ref().unwrap_or(if speed > 0.01 {
+                            &animation_names.walk
+                        } else {
+                            &animation_names.idle
+                        }),
+                        0.1,
+                    ),
+                    AnimationState::Dashing => (
+                        animation_names
+                            .dash
+                            .as_ref()
+                            .unwrap_or(&animation_names.aerial),
+                        0.05,
+                    ),
+                    AnimationState::Sliding => (
+                        animation_names
+                            .slide
+                            .as_ref()
+                            .unwrap_or(&animation_names.aerial),
+                        0.2,
+                    ),
+                    AnimationState::Climbing(_speed) => (
+                        animation_names
+                            .climb
+                            .as_ref()
+                            .unwrap_or(&animation_names.walk),
+                        0.1,
+                    ),
+                    AnimationState::Swimming => (
+                        animation_names
+                            .swim
+                            .as_ref()
+                            .unwrap_or(&animation_names.walk),
+                        0.2,
+                    ),
+                };
+                animation_player
+                    .play_with_transition(
+                        animations
+                            .named_animations
+                            .get(name)
+                            .with_context(|| format!("Character has no animation named {name}"))?
+                            .clone_weak(),
+                        Duration::from_secs_f32(transition),
+                    )
+                    .repeat();
+            }
         }
     }
     Ok(())
@@ -0,0 +1,252 @@
+use crate::movement::character_controller::{Climb, Crouch, Dash, FloatHeight, Jump, Walk};
+use crate::util::Vec3Ext;
+use bevy::prelude::*;
+use bevy_rapier3d::{
+    parry::bounding_volume::Aabb,
+    prelude::{Collider as RapierCollider, *},
+    utils,
+};
+use bevy_tnua::{
+    prelude::*, TnuaBasis, TnuaBasisContext, TnuaMotor, TnuaProximitySensor, TnuaVelChange,
+};
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Climbable>();
+}
+
+/// A ladder, a vine or any other surface characters can climb. Can be added in Blender.
+/// The climbable area are the bounding boxes of the colliders of the object and its children.
+#[derive(Debug, Clone, Eq, PartialEq, Component, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize)]
+pub(crate) struct Climbable;
+
+/// Holds the character in front of a climbable surface, cancelling gravity, and moves it with the desired velocity.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClimbBasis {
+    pub(crate) desired_velocity: Vec3,
+    /// The direction the character faces, usually towards the climbable
+    pub(crate) desired_forward: Vec3,
+    pub(crate) acceleration: f32,
+    /// Turning speed in rad/s
+    pub(crate) turning_angvel: f32,
+    /// How far below the character the ground sensor looks for a floor to step off onto
+    pub(crate) sensor_range: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ClimbBasisState {
+    pub(crate) velocity: Vec3,
+}
+
+impl TnuaBasis for ClimbBasis {
+    const NAME: &'static str = "ClimbBasis";
+    type State = ClimbBasisState;
+
+    fn apply(&self, state: &mut Self::State, ctx: TnuaBasisContext, motor: &mut TnuaMotor) {
+        state.velocity = ctx.tracker.velocity;
+        let velocity_change = (self.desired_velocity - ctx.tracker.velocity)
+            .clamp_length_max(self.acceleration * ctx.frame_duration);
+        motor.lin = TnuaVelChange {
+            acceleration: -ctx.tracker.gravity,
+            boost: velocity_change,
+        };
+
+        let current_forward = (ctx.tracker.rotation * Vec3::NEG_Z).horizontal();
+        let desired_forward = self.desired_forward.horizontal();
+        let desired_angvel = if current_forward == Vec3::ZERO || desired_forward == Vec3::ZERO {
+            0.
+        } else {
+            // Turning around the up axis is clockwise when seen from above
+            let angle = -Vec2::new(current_forward.x, current_forward.z)
+                .angle_between(Vec2::new(desired_forward.x, desired_forward.z));
+            (angle / ctx.frame_duration).clamp(-self.turning_angvel, self.turning_angvel)
+        };
+        motor.ang = TnuaVelChange::boost(Vec3::Y * desired_angvel - ctx.tracker.angvel);
+    }
+
+    fn proximity_sensor_cast_range(&self, _state: &Self::State) -> f32 {
+        self.sensor_range
+    }
+
+    fn displacement(&self, _state: &Self::State) -> Option<Vec3> {
+        None
+    }
+
+    fn effective_velocity(&self, state: &Self::State) -> Vec3 {
+        state.velocity
+    }
+
+    fn vertical_velocity(&self, state: &Self::State) -> f32 {
+        state.velocity.y
+    }
+
+    fn neutralize(&mut self) {
+        self.desired_velocity = Vec3::ZERO;
+        self.desired_forward = Vec3::ZERO;
+    }
+
+    fn is_airborne(&self, _state: &Self::State) -> bool {
+        false
+    }
+
+    fn violate_coyote_time(&self, _state: &mut Self::State) {}
+}
+
+/// Mounts characters that interact with or walk into a [`Climbable`] and feeds them a [`ClimbBasis`] instead of a walk.
+/// Walking towards the climbable climbs up, walking away from it climbs down.
+/// Characters step off at the top once they cleared the ledge, at the bottom once they reach the ground, and when they jump.
+pub(super) fn apply_climbing(
+    climbables: Query<Entity, With<Climbable>>,
+    children: Query<&Children>,
+    colliders: Query<(&RapierCollider, &GlobalTransform)>,
+    mut character_query: Query<(
+        &mut TnuaController,
+        &mut Climb,
+        &mut Walk,
+        &mut Jump,
+        &mut Crouch,
+        &mut Dash,
+        &Transform,
+        &FloatHeight,
+        &TnuaProximitySensor,
+    )>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("apply_climbing").entered();
+    let climbable_areas: Vec<_> = climbables
+        .iter()
+        .flat_map(|climbable| {
+            iter::once(climbable)
+                .chain(children.iter_descendants(climbable))
+                .filter_map(|entity| colliders.get(entity).ok())
+                .map(move |(collider, transform)| {
+                    let iso = utils::transform_to_iso(&transform.compute_transform());
+                    (climbable, collider.raw.compute_aabb(&iso))
+                })
+        })
+        .collect();
+    for (
+        mut controller,
+        mut climb,
+        mut walk,
+        mut jump,
+        mut crouch,
+        mut dash,
+        transform,
+        float_height,
+        sensor,
+    ) in &mut character_query
+    {
+        let position = transform.translation;
+        let mount_requested = std::mem::take(&mut climb.mount_requested);
+        let direction = walk.direction.unwrap_or_default().horizontal();
+        let in_reach = climbable_areas.iter().find(|(climbable, aabb)| {
+            let can_reach = distance_to(aabb, position) <= climb.reach
+                && position.y <= aabb.maxs.y + climb.ledge_clearance + climb.reach;
+            if climb.climbing {
+                climb.climbable == Some(*climbable) && can_reach
+            } else {
+                can_reach && position.y < aabb.maxs.y
+            }
+        });
+        let Some((climbable, aabb)) = in_reach else {
+            climb.climbing = false;
+            climb.climbable = None;
+            continue;
+        };
+        let towards_climbable = (closest_point(aabb, position) - position)
+            .horizontal()
+            .try_normalize()
+            .unwrap_or_else(|| Vec3::from(transform.forward()).horizontal());
+        let vertical_input = direction.dot(towards_climbable);
+
+        if !climb.climbing {
+            if mount_requested || vertical_input > 0.7 {
+                climb.climbing = true;
+                climb.climbable = Some(*climbable);
+            } else {
+                continue;
+            }
+        }
+
+        let ground_below = sensor
+            .output
+            .as_ref()
+            .is_some_and(|output| output.proximity <= float_height.0 + climb.ledge_clearance);
+        let above_ledge = position.y >= aabb.maxs.y + climb.ledge_clearance;
+        let reached_bottom = vertical_input < 0. && ground_below && !above_ledge;
+        let stepped_onto_ledge = above_ledge && ground_below;
+        if std::mem::take(&mut jump.requested) || reached_bottom || stepped_onto_ledge {
+            climb.climbing = false;
+            climb.climbable = None;
+            continue;
+        }
+
+        let desired_velocity = if above_ledge {
+            // Step forward over the ledge instead of climbing into the air
+            towards_climbable * climb.speed
+        } else {
+            Vec3::Y * vertical_input * climb.speed
+        };
+        controller.basis(ClimbBasis {
+            desired_velocity,
+            desired_forward: towards_climbable,
+            acceleration: walk.acceleration,
+            turning_angvel: walk.turning_speed,
+            sensor_range: float_height.0 + climb.ledge_clearance,
+        });
+        walk.direction = None;
+        crouch.requested = false;
+        dash.requested = false;
+    }
+}
+
+fn closest_point(aabb: &Aabb, position: Vec3) -> Vec3 {
+    position.clamp(
+        Vec3::new(aabb.mins.x, aabb.mins.y, aabb.mins.z),
+        Vec3::new(aabb.maxs.x, aabb.maxs.y, aabb.maxs.z),
+    )
+}
+
+/// Horizontal distance, as characters reach the climbable at any height it spans
+fn distance_to(aabb: &Aabb, position: Vec3) -> f32 {
+    (closest_point(aabb, position) - position)
+        .horizontal()
+        .length()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::movement::character_controller::Climb;
+    use crate::test_harness::{TestApp, TestEntity};
+    use bevy::prelude::*;
+
+    #[test]
+    fn player_climbs_onto_ledge() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        app.spawn(TestEntity::climbable(Vec3::new(2., 1.5, 2.)).at(Vec3::new(0., 3., -3.5)));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.ticks(120);
+        // Height of the player's center above whatever it stands on
+        let standing_height = app.translation(player).y;
+        let climbing = |app: &TestApp| app.app.world.get::<Climb>(player).unwrap().climbing;
+
+        app.press(KeyCode::KeyW);
+        let mounted = app.tick_until(120, |app| climbing(app));
+        assert!(mounted.is_some(), "Player did not mount the wall");
+
+        let dismounted = app.tick_until(640, |app| !climbing(app));
+        assert!(dismounted.is_some(), "Player did not step off the wall");
+        app.release(KeyCode::KeyW);
+        app.ticks(64);
+
+        let translation = app.translation(player);
+        assert!(
+            (translation.y - (3. + standing_height)).abs() < 0.05 && translation.z < -1.5,
+            "Player should stand on top of the wall, but is at {translation}"
+        );
+    }
+}
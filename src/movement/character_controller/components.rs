@@ -1,12 +1,29 @@
-use crate::movement::character_controller::AnimationState;
+use crate::movement::{
+    character_controller::AnimationState,
+    physics::{CollisionLayer, CollisionLayers},
+};
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
-use bevy_tnua::{prelude::*, TnuaAnimatingState};
+use bevy_tnua::{
+    control_helpers::{TnuaCrouchEnforcer, TnuaSimpleAirActionsCounter},
+    prelude::*,
+    TnuaAnimatingState,
+};
 use bevy_tnua_rapier3d::*;
 use serde::{Deserialize, Serialize};
 
 pub(super) fn plugin(app: &mut App) {
-    app.register_type::<Jump>().register_type::<Walk>();
+    app.register_type::<Jump>()
+        .register_type::<Walk>()
+        .register_type::<Push>()
+        .register_type::<Crouch>()
+        .register_type::<Dash>()
+        .register_type::<SlopeSlide>()
+        .register_type::<Stamina>()
+        .register_type::<Swim>()
+        .register_type::<Climb>()
+        .register_type::<AgentRadius>()
+        .add_event::<StaminaEvent>();
 }
 
 #[derive(Bundle)]
@@ -14,14 +31,25 @@ pub(crate) struct CharacterControllerBundle {
     pub(crate) walking: Walk,
     pub(crate) sprinting: Sprinting,
     pub(crate) jumping: Jump,
+    pub(crate) crouching: Crouch,
+    pub(crate) dashing: Dash,
+    pub(crate) slope_sliding: SlopeSlide,
+    pub(crate) stamina: Stamina,
+    pub(crate) swimming: Swim,
+    pub(crate) climbing: Climb,
+    pub(crate) pushing: Push,
     pub(crate) collider: Collider,
     pub(crate) rigid_body: RigidBody,
+    pub(crate) gravity_scale: GravityScale,
     pub(crate) locked_axes: LockedAxes,
-    pub(crate) collision_layers: CollisionGroups,
+    pub(crate) collision_layers: CollisionLayers,
     pub(crate) tnua_sensor_shape: TnuaRapier3dSensorShape,
     pub(crate) tnua_controller: TnuaControllerBundle,
+    pub(crate) tnua_crouch_enforcer: TnuaCrouchEnforcer,
+    pub(crate) tnua_air_actions_counter: TnuaSimpleAirActionsCounter,
     pub(crate) tnua_rapier3d_io: TnuaRapier3dIOBundle,
     pub(crate) float_height: FloatHeight,
+    pub(crate) agent_radius: AgentRadius,
     pub(crate) animation_state: TnuaAnimatingState<AnimationState>,
 
     pub(crate) colliding_entities: CollidingEntities,
@@ -39,27 +67,42 @@ impl CharacterControllerBundle {
             walking: default(),
             sprinting: default(),
             jumping: default(),
+            crouching: default(),
+            dashing: default(),
+            slope_sliding: default(),
+            stamina: default(),
+            swimming: default(),
+            climbing: default(),
+            pushing: default(),
             collider: Collider::capsule_z(height, radius),
             rigid_body: RigidBody::Dynamic,
+            gravity_scale: default(),
             locked_axes: LockedAxes::ROTATION_LOCKED_X | LockedAxes::ROTATION_LOCKED_Z,
-            collision_layers: CollisionGroups::new(
-                Group::GROUP_2,
-                Group::GROUP_1 | Group::GROUP_2 | Group::GROUP_3 | Group::GROUP_5,
-                // [CollisionLayer::Character],
-                // [
-                //     CollisionLayer::Player,
-                //     CollisionLayer::Character,
-                //     CollisionLayer::Terrain,
-                //     CollisionLayer::Sensor,
-                // ],
+            collision_layers: CollisionLayers::new(
+                CollisionLayer::Character,
+                CollisionLayer::Player
+                    | CollisionLayer::Character
+                    | CollisionLayer::Terrain
+                    | CollisionLayer::Sensor
+                    | CollisionLayer::Prop,
             ),
             tnua_sensor_shape: TnuaRapier3dSensorShape(Collider::capsule_z(
                 height * 0.95,
                 radius * 0.95,
             )),
             tnua_controller: default(),
+            tnua_air_actions_counter: default(),
+            // Keeps crouching characters from standing up under a ceiling
+            tnua_crouch_enforcer: TnuaCrouchEnforcer::new(Vec3::ZERO, move |commands| {
+                commands.insert(TnuaRapier3dSensorShape(Collider::cylinder(
+                    0.,
+                    radius * 0.95,
+                )));
+            }),
             tnua_rapier3d_io: default(),
             float_height: FloatHeight((radius / 2.) * scale_y),
+            // Half the length of the lying capsule, so the footprint covers the whole body
+            agent_radius: AgentRadius(height / 2. + radius),
             animation_state: default(),
             colliding_entities: default(),
             active_collision_types: default(),
@@ -69,11 +112,21 @@ impl CharacterControllerBundle {
     }
 }
 
+/// Tunable from the `[movement]` section of the game config.
 #[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Walk {
     /// Top speed on the ground
     pub(crate) speed: f32,
+    pub(crate) acceleration: f32,
+    pub(crate) air_acceleration: f32,
+    /// Turning speed in rad/s
+    pub(crate) turning_speed: f32,
+    /// How far below the float height the character still snaps to the ground
+    pub(crate) cling_distance: f32,
+    /// Speed multiplier when walking backwards in first person
+    pub(crate) backwards_modifier: f32,
     /// Direction in which we want to walk and turn this tick.
     pub(crate) direction: Option<Vec3>,
 }
@@ -82,22 +135,42 @@ impl Default for Walk {
     fn default() -> Self {
         Self {
             speed: 8.,
+            acceleration: 60.,
+            air_acceleration: 20.,
+            turning_speed: 10.,
+            cling_distance: 0.1,
+            backwards_modifier: 0.7,
             direction: None,
         }
     }
 }
 
+/// Tunable from the `[movement.<archetype>.jump]` section of the game config.
 #[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Jump {
     /// The full height of the jump, if the player does not release the button
     pub(crate) height: f32,
-    /// Was jump requested this frame?
+    /// Seconds after walking off a ledge in which a jump still starts from the ground
+    pub(crate) coyote_time: f32,
+    /// Seconds a jump requested shortly before landing is remembered
+    pub(crate) buffer_time: f32,
+    /// Additional jumps possible before landing again
+    pub(crate) max_air_jumps: u32,
+    /// Extra downwards acceleration right after taking off, for a snappier start
+    pub(crate) takeoff_extra_gravity: f32,
+    /// Extra downwards acceleration while rising after the button was released, making short taps jump lower
+    pub(crate) release_extra_gravity: f32,
+    /// Extra downwards acceleration while falling
+    pub(crate) fall_extra_gravity: f32,
+    /// Is jump requested this frame? Has to be requested every frame for as long as the button is held.
     pub(crate) requested: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
 pub(crate) struct Sprinting {
     /// The speed multiplier when sprinting
     pub(crate) multiplier: f32,
@@ -114,17 +187,275 @@ impl Default for Sprinting {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Crouch {
+    /// Added to the float height while crouching, so it should be negative
+    pub(crate) float_offset: f32,
+    /// Vertical scale of the collider while crouching
+    pub(crate) collider_scale: f32,
+    /// The speed multiplier when crouching
+    pub(crate) speed_multiplier: f32,
+    /// Is crouching requested? Stays crouched under low ceilings even when not requested.
+    pub(crate) requested: bool,
+}
+
+impl Default for Crouch {
+    fn default() -> Self {
+        Self {
+            float_offset: -0.1,
+            collider_scale: 0.6,
+            speed_multiplier: 0.5,
+            requested: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Dash {
+    /// Distance covered by a single dash
+    pub(crate) distance: f32,
+    pub(crate) speed: f32,
+    /// Seconds until the next dash is possible
+    pub(crate) cooldown: f32,
+    /// Dashes possible before landing again
+    pub(crate) max_air_dashes: u32,
+    /// Was a dash requested this frame?
+    pub(crate) requested: bool,
+    pub(crate) cooldown_remaining: f32,
+    pub(crate) air_dashes_used: u32,
+}
+
+impl Default for Dash {
+    fn default() -> Self {
+        Self {
+            distance: 4.,
+            speed: 20.,
+            cooldown: 0.8,
+            max_air_dashes: 1,
+            requested: false,
+            cooldown_remaining: 0.,
+            air_dashes_used: 0,
+        }
+    }
+}
+
+/// Makes characters slide down slopes that are too steep to stand on.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct SlopeSlide {
+    /// Steepest slope in radians the character can still stand on
+    pub(crate) max_slope: f32,
+    /// Is the character sliding down a slope right now?
+    pub(crate) sliding: bool,
+}
+
+impl Default for SlopeSlide {
+    fn default() -> Self {
+        Self {
+            max_slope: 50_f32.to_radians(),
+            sliding: false,
+        }
+    }
+}
+
+/// Spent by sprinting and jumping. Once exhausted, the character can't sprint until it fully recovered.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Stamina {
+    pub(crate) max: f32,
+    pub(crate) current: f32,
+    /// Regenerated per second
+    pub(crate) regen_rate: f32,
+    /// Seconds after spending stamina before it regenerates
+    pub(crate) regen_delay: f32,
+    pub(crate) sprint_cost_per_second: f32,
+    pub(crate) jump_cost: f32,
+    pub(crate) regen_delay_remaining: f32,
+    pub(crate) exhausted: bool,
+}
+
+impl Default for Stamina {
+    fn default() -> Self {
+        Self {
+            max: 100.,
+            current: 100.,
+            regen_rate: 20.,
+            regen_delay: 1.,
+            sprint_cost_per_second: 15.,
+            jump_cost: 10.,
+            regen_delay_remaining: 0.,
+            exhausted: false,
+        }
+    }
+}
+
+impl Stamina {
+    pub(crate) fn can_afford(&self, cost: f32) -> bool {
+        !self.exhausted && self.current >= cost
+    }
+}
+
+/// Sent when a character's [`Stamina`] runs out or is fully recovered, e.g. to show or hide a stamina bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub(crate) enum StaminaEvent {
+    Exhausted(Entity),
+    Recovered(Entity),
+}
+
+/// Lets a character swim in [`WaterVolume`](super::WaterVolume)s.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Swim {
+    /// How far below the surface the character's center floats
+    pub(crate) float_depth: f32,
+    /// Height of the head above the character's center, used to tell whether it is submerged
+    pub(crate) head_height: f32,
+    /// The speed multiplier when swimming
+    pub(crate) speed_multiplier: f32,
+    /// Vertical speed when diving or rising
+    pub(crate) dive_speed: f32,
+    /// Was diving requested this frame?
+    pub(crate) dive_requested: bool,
+    /// Was rising to the surface requested this frame?
+    pub(crate) rise_requested: bool,
+    pub(crate) swimming: bool,
+    /// Is the character's head below the surface?
+    pub(crate) submerged: bool,
+    /// The water volume the character swims in
+    #[serde(skip)]
+    pub(crate) water: Option<Entity>,
+    /// Height of the surface of the water the character is in, even if it is too shallow to swim
+    pub(crate) surface: Option<f32>,
+}
+
+impl Default for Swim {
+    fn default() -> Self {
+        Self {
+            float_depth: 0.3,
+            head_height: 0.5,
+            speed_multiplier: 0.5,
+            dive_speed: 3.,
+            dive_requested: false,
+            rise_requested: false,
+            swimming: false,
+            submerged: false,
+            water: None,
+            surface: None,
+        }
+    }
+}
+
+/// Lets a character climb [`Climbable`](super::Climbable)s.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Climb {
+    /// Speed in m/s when climbing up or down
+    pub(crate) speed: f32,
+    /// Horizontal distance from the character's center within which it can mount a climbable
+    pub(crate) reach: f32,
+    /// How far above the top of the climbable the character's center climbs before stepping forward,
+    /// so that it clears the ledge
+    pub(crate) ledge_clearance: f32,
+    /// Was mounting requested this frame, e.g. by interacting with the climbable?
+    pub(crate) mount_requested: bool,
+    pub(crate) climbing: bool,
+    /// The climbable the character is on
+    #[serde(skip)]
+    pub(crate) climbable: Option<Entity>,
+}
+
+impl Default for Climb {
+    fn default() -> Self {
+        Self {
+            speed: 2.,
+            reach: 1.,
+            ledge_clearance: 0.5,
+            mount_requested: false,
+            climbing: false,
+            climbable: None,
+        }
+    }
+}
+
+/// How hard a character pushes the dynamic rigid bodies it walks into.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Push {
+    /// Force in N applied per m/s the character moves towards the pushed body
+    pub(crate) force_per_speed: f32,
+    /// Upper bound for the force in N, so that sprinting into a prop doesn't launch it
+    pub(crate) max_force: f32,
+}
+
+impl Default for Push {
+    fn default() -> Self {
+        Self {
+            force_per_speed: 60.,
+            max_force: 400.,
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
 #[reflect(Component, Serialize, Deserialize)]
 /// Must be larger than the height of the entity's center from the bottom of its
 /// collider, or else the character will not float and Tnua will not work properly
 pub(crate) struct FloatHeight(pub(crate) f32);
 
+/// Horizontal radius of the character's footprint, used by NPCs to keep their distance from other characters
+#[derive(Debug, Default, Clone, Copy, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+pub(crate) struct AgentRadius(pub(crate) f32);
+
 impl Default for Jump {
     fn default() -> Self {
         Self {
             height: 1.0,
+            coyote_time: 0.15,
+            buffer_time: 0.2,
+            max_air_jumps: 0,
+            takeoff_extra_gravity: 10.0,
+            release_extra_gravity: 40.0,
+            fall_extra_gravity: 20.0,
             requested: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blender_markers_fill_in_missing_fields() {
+        let crouch: Crouch = ron::from_str("(speed_multiplier: 0.25)").unwrap();
+        assert_eq!(
+            crouch,
+            Crouch {
+                speed_multiplier: 0.25,
+                ..default()
+            }
+        );
+        let dash: Dash = ron::from_str("(distance: 6.)").unwrap();
+        assert_eq!(
+            dash,
+            Dash {
+                distance: 6.,
+                ..default()
+            }
+        );
+        let slope_slide: SlopeSlide = ron::from_str("()").unwrap();
+        assert_eq!(slope_slide, SlopeSlide::default());
+        let push: Push = ron::from_str("()").unwrap();
+        assert_eq!(push, Push::default());
+    }
+}
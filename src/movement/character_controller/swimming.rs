@@ -0,0 +1,197 @@
+use crate::movement::character_controller::{Crouch, Jump, Swim};
+use crate::physics_time::{PhysicsSchedule, PhysicsTime};
+use bevy::prelude::*;
+use bevy_rapier3d::{
+    parry::bounding_volume::Aabb,
+    prelude::{Collider as RapierCollider, *},
+    utils,
+};
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<WaterVolume>()
+        .add_event::<SplashEvent>()
+        .add_systems(
+            PhysicsSchedule,
+            (detect_water, apply_buoyancy)
+                .chain()
+                .before(PhysicsSet::SyncBackend),
+        );
+}
+
+/// A body of water characters swim in. Can be added in Blender.
+/// The water fills the bounding boxes of the colliders of the object and its children, so add a sensor `Collider` marker next to it.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct WaterVolume {
+    /// Upwards acceleration in m/s² per meter a character is below its floating depth
+    #[reflect(default)]
+    pub(crate) buoyancy: f32,
+    /// How quickly the water slows down vertical movement
+    #[reflect(default)]
+    pub(crate) drag: f32,
+}
+
+impl Default for WaterVolume {
+    fn default() -> Self {
+        Self {
+            buoyancy: 10.,
+            drag: 5.,
+        }
+    }
+}
+
+/// Sent when a character falls or walks into deep enough water to start swimming.
+#[derive(Debug, Clone, Copy, PartialEq, Event)]
+pub(crate) struct SplashEvent {
+    pub(crate) character: Entity,
+    /// Where the character broke through the surface
+    pub(crate) position: Vec3,
+}
+
+/// While swimming, jumping rises to the surface and crouching dives instead.
+pub(super) fn read_swim_input(mut character_query: Query<(&mut Swim, &mut Jump, &mut Crouch)>) {
+    for (mut swim, mut jump, mut crouch) in &mut character_query {
+        if !swim.swimming {
+            swim.rise_requested = false;
+            swim.dive_requested = false;
+            continue;
+        }
+        swim.rise_requested = std::mem::take(&mut jump.requested);
+        swim.dive_requested = std::mem::take(&mut crouch.requested);
+    }
+}
+
+/// Characters start swimming once their center is deeper than [`Swim::float_depth`] and stop when it leaves the water,
+/// so that bobbing at the surface doesn't toggle between swimming and falling.
+fn detect_water(
+    volumes: Query<Entity, With<WaterVolume>>,
+    children: Query<&Children>,
+    colliders: Query<(&RapierCollider, &GlobalTransform)>,
+    mut characters: Query<(Entity, &mut Swim, &mut GravityScale, &Transform)>,
+    mut splash_events: EventWriter<SplashEvent>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("detect_water").entered();
+    let water: Vec<_> = volumes
+        .iter()
+        .flat_map(|volume| {
+            iter::once(volume)
+                .chain(children.iter_descendants(volume))
+                .filter_map(|entity| colliders.get(entity).ok())
+                .map(move |(collider, transform)| {
+                    let iso = utils::transform_to_iso(&transform.compute_transform());
+                    (volume, collider.raw.compute_aabb(&iso))
+                })
+        })
+        .collect();
+    for (character, mut swim, mut gravity_scale, transform) in &mut characters {
+        let position = transform.translation;
+        let surrounding_water = water
+            .iter()
+            .filter(|(_, aabb)| contains(aabb, position))
+            .map(|(volume, aabb)| (*volume, aabb.maxs.y))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        let was_swimming = swim.swimming;
+        swim.swimming = surrounding_water.is_some_and(|(_, surface)| {
+            let required_depth = if was_swimming { 0. } else { swim.float_depth };
+            position.y < surface - required_depth
+        });
+        swim.submerged =
+            surrounding_water.is_some_and(|(_, surface)| position.y + swim.head_height < surface);
+        swim.water = surrounding_water
+            .filter(|_| swim.swimming)
+            .map(|(volume, _)| volume);
+        swim.surface = surrounding_water.map(|(_, surface)| surface);
+
+        if swim.swimming && !was_swimming {
+            if let Some((_, surface)) = surrounding_water {
+                splash_events.send(SplashEvent {
+                    character,
+                    position: Vec3::new(position.x, surface, position.z),
+                });
+            }
+        }
+        // The water carries swimmers instead
+        let target_gravity_scale = if swim.swimming { 0. } else { 1. };
+        if gravity_scale.0 != target_gravity_scale {
+            gravity_scale.0 = target_gravity_scale;
+        }
+    }
+}
+
+fn contains(aabb: &Aabb, position: Vec3) -> bool {
+    (aabb.mins.x..=aabb.maxs.x).contains(&position.x)
+        && (aabb.mins.y..=aabb.maxs.y).contains(&position.y)
+        && (aabb.mins.z..=aabb.maxs.z).contains(&position.z)
+}
+
+/// Tnua only steers swimmers horizontally, as it sees them as airborne.
+/// Vertically, they are pulled towards their floating depth like by a damped spring, or dive and rise when requested.
+fn apply_buoyancy(
+    time: Res<PhysicsTime>,
+    volumes: Query<&WaterVolume>,
+    mut characters: Query<(&Swim, &Transform, &mut Velocity)>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("apply_buoyancy").entered();
+    let dt = time.context().timestep.as_secs_f32();
+    for (swim, transform, mut velocity) in &mut characters {
+        let (Some(water), Some(surface)) = (swim.water, swim.surface) else {
+            continue;
+        };
+        let Ok(volume) = volumes.get(water) else {
+            continue;
+        };
+        let depth = surface - swim.float_depth - transform.translation.y;
+        let vertical_speed = velocity.linvel.y;
+        let acceleration = if swim.dive_requested {
+            (-swim.dive_speed - vertical_speed) * volume.drag
+        } else if swim.rise_requested && depth > 0. {
+            (swim.dive_speed - vertical_speed) * volume.drag
+        } else {
+            depth * volume.buoyancy - vertical_speed * volume.drag
+        };
+        velocity.linvel.y += acceleration * dt;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TestApp, TestEntity};
+
+    #[test]
+    fn player_floats_and_dives() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        app.spawn(TestEntity::water(Vec3::new(10., 2., 10.)).at(Vec3::new(0., 4., 0.)));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 6., 0.)));
+
+        app.ticks(320);
+
+        let floating = app.translation(player);
+        let swim = app.app.world.get::<Swim>(player).unwrap();
+        assert!(swim.swimming, "Player should swim at {floating}");
+        let float_height = 4. - swim.float_depth;
+        assert!(
+            (floating.y - float_height).abs() < 0.05,
+            "Player should float at a height of {float_height}, but is at {floating}"
+        );
+
+        app.press(KeyCode::ControlLeft);
+        app.ticks(32);
+        let dived = app.translation(player);
+        assert!(
+            dived.y < floating.y - 0.5,
+            "Player should dive, but went from {floating} to {dived}"
+        );
+        assert!(app.app.world.get::<Swim>(player).unwrap().submerged);
+
+        app.release(KeyCode::ControlLeft);
+        let surfaced = app.tick_until(320, |app| app.translation(player).y > 3.);
+        assert!(surfaced.is_some(), "Player should float back up");
+    }
+}
@@ -2,118 +2,641 @@
 use crate::dev::dev_editor::DevEditorWindow;
 use crate::util::error;
 use crate::{
-    level_instantiation::on_spawn::{player, Npc, Player},
-    movement::character_controller::Walk,
+    movement::character_controller::{AgentRadius, Climb, Jump, Walk},
     util::{F32Ext, Vec3Ext},
     GameSystemSet,
 };
 #[cfg(feature = "dev")]
 use anyhow::Context;
-use bevy::prelude::*;
-use bevy_rapier3d::prelude::Collider;
+use bevy::{
+    prelude::*,
+    tasks::{futures_lite::future, AsyncComputeTaskPool, Task},
+    utils::HashMap,
+};
+use bevy_rapier3d::{
+    prelude::{Collider, Velocity},
+    utils,
+};
+use off_mesh_link::{plan_route, NavLink, Waypoint};
+pub(crate) use off_mesh_link::{LinkTraversal, OffMeshLink};
 #[cfg(feature = "dev")]
-use oxidized_navigation::debug_draw::{DrawNavMesh, DrawPath, OxidizedNavigationDebugDrawPlugin};
+use oxidized_navigation::debug_draw::DrawPath;
 use oxidized_navigation::{
-    query::{find_polygon_path, perform_string_pulling_on_path},
-    NavMesh, NavMeshSettings, OxidizedNavigationPlugin,
+    query::find_path, tiles::NavMeshTiles, NavMeshAffector, NavMeshSettings,
+    OxidizedNavigationPlugin,
 };
+pub(crate) use profile::NavAgentProfile;
+use profile::NavMeshes;
+use std::sync::{Arc, RwLock};
+
+mod off_mesh_link;
+mod profile;
 
-/// Manually tweaked
-const CELL_WIDTH: f32 = 0.4 * player::RADIUS;
+/// Upper bound for path queries started per frame, so that many NPCs replanning at once don't spike the frame time
+const MAX_PATH_REQUESTS_PER_FRAME: usize = 4;
+/// How far the target has to move away from the end of the current path before the path is replanned
+const REPLAN_DISTANCE: f32 = 1.0;
+/// Seconds before a failed path query is retried, e.g. because the navmesh was still being generated
+const RETRY_DELAY: f32 = 1.0;
+/// Extra distance agents keep between each other on top of their radii
+const AVOIDANCE_MARGIN: f32 = 0.3;
+/// Seconds ahead agents look for characters they are about to bump into
+const AVOIDANCE_LOOKAHEAD: f32 = 1.0;
+/// How strongly agents that are already too close push away from each other, relative to following their path
+const SEPARATION_WEIGHT: f32 = 1.5;
+/// How quickly the avoidance steering follows changes, in 1/s. Smooths out jitter in crowds.
+const AVOIDANCE_RESPONSIVENESS: f32 = 6.;
+/// Seconds oxidized_navigation roughly needs to rebuild a tile after the colliders in it changed
+const TILE_REBUILD_TIME: f32 = 0.5;
+/// Seconds after which an agent gives up on traversing an off-mesh link, e.g. because it jumped short, and replans
+const LINK_TIMEOUT: f32 = 5.0;
+/// How high above the end of a jump link an agent's center has to be before it stops holding the jump
+const JUMP_CLEARANCE: f32 = 1.5;
 
-/// Handles NPC pathfinding. Currently, all entities with the [`Npc`] component will follow the [`Player`].
+/// Handles NPC pathfinding. All entities with a [`NavTarget`] walk towards it along the navmesh.
+/// Paths are planned asynchronously, cached per agent and only replanned when the target moved
+/// or the navmesh changed along the path. At most [`MAX_PATH_REQUESTS_PER_FRAME`] paths are requested per frame.
+/// Agents steer around other characters on their way, so that groups don't clump together.
+/// Each agent paths on the navmesh of its [`NavAgentProfile`] and takes [`OffMeshLink`]s where they help.
 pub(super) fn plugin(app: &mut App) {
-    // consts manually tweaked
-    app.add_plugins(OxidizedNavigationPlugin::<Collider>::new(NavMeshSettings {
-        cell_width: CELL_WIDTH,
-        cell_height: 0.5 * CELL_WIDTH,
-        tile_width: 170,
-        world_half_extents: 250.0,
-        world_bottom_bound: -20.0,
-        max_traversable_slope_radians: (40.0_f32 - 0.1).to_radians(),
-        walkable_height: 25,
-        walkable_radius: 4,
-        step_height: 3,
-        min_region_area: 30,
-        merge_region_area: 500,
-        max_contour_simplification_error: 1.3,
-        max_edge_length: 100,
-        max_tile_generation_tasks: None,
-    }))
-    .add_systems(
-        Update,
-        query_mesh.pipe(error).in_set(GameSystemSet::Navigation),
-    );
-    #[cfg(feature = "dev")]
-    app.add_plugins(OxidizedNavigationDebugDrawPlugin)
-        .add_systems(Update, draw_navmesh.pipe(error));
+    app.register_type::<NavTarget>()
+        .init_resource::<NavMeshChanges>()
+        .add_plugins((
+            OxidizedNavigationPlugin::<Collider>::new(
+                NavAgentProfile::Humanoid.nav_mesh_settings(),
+            ),
+            profile::plugin,
+            off_mesh_link::plugin,
+        ))
+        .add_systems(
+            Update,
+            (
+                init_nav_paths,
+                track_nav_mesh_changes,
+                request_paths,
+                receive_paths.pipe(error),
+                follow_paths,
+                avoid_crowds,
+            )
+                .chain()
+                .in_set(GameSystemSet::Navigation),
+        );
+}
+
+/// Where an NPC wants to go. Set by its [`Behavior`](super::behavior::Behavior).
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect, Default)]
+#[reflect(Component)]
+pub(crate) struct NavTarget {
+    pub(crate) position: Option<Vec3>,
+    /// Stops walking when this close to the target
+    pub(crate) stop_distance: f32,
+}
+
+impl NavTarget {
+    pub(crate) fn at(position: Vec3) -> Self {
+        Self {
+            position: Some(position),
+            stop_distance: 0.,
+        }
+    }
 }
 
-fn query_mesh(
+/// The path an agent follows towards its [`NavTarget`].
+#[derive(Debug, Component, Default)]
+struct NavPath {
+    waypoints: Vec<Waypoint>,
+    /// Index of the waypoint the agent currently walks towards
+    next: usize,
+    /// The target position the path was planned for
+    destination: Option<Vec3>,
+    /// Navmesh tiles the waypoints lie in
+    tiles: Vec<UVec2>,
+    /// Elapsed seconds when the path was planned
+    planned_at: f32,
+    /// Set when no path was found
+    retry_at: Option<f32>,
+    request: Option<PathRequest>,
+    /// Elapsed seconds when the agent started traversing the off-mesh link towards the next waypoint
+    link_started_at: Option<f32>,
+    /// Whether the agent let go of the jump of the current jump link
+    jump_released: bool,
+}
+
+#[derive(Debug)]
+struct PathRequest {
+    destination: Vec3,
+    task: Task<Option<Vec<Waypoint>>>,
+}
+
+/// When the tiles of the navmesh were last invalidated by colliders moving, appearing or disappearing.
+#[derive(Debug, Resource, Default)]
+struct NavMeshChanges {
+    tiles: HashMap<UVec2, f32>,
+    /// A collider was removed, which could have been anywhere
+    everything_changed_at: Option<f32>,
+}
+
+impl NavMeshChanges {
+    /// Whether a tile of the path was rebuilt since the path was planned.
+    fn invalidates(&self, path: &NavPath, now: f32) -> bool {
+        let rebuilt_since_planning = |changed_at: f32| {
+            let rebuilt_at = changed_at + TILE_REBUILD_TIME;
+            path.planned_at < rebuilt_at && now >= rebuilt_at
+        };
+        self.everything_changed_at
+            .is_some_and(rebuilt_since_planning)
+            || path
+                .tiles
+                .iter()
+                .filter_map(|tile| self.tiles.get(tile))
+                .any(|changed_at| rebuilt_since_planning(*changed_at))
+    }
+}
+
+/// The smoothed steering an agent adds to its path direction to avoid other characters.
+#[derive(Debug, Clone, Copy, PartialEq, Component, Default)]
+struct Avoidance(Vec3);
+
+fn init_nav_paths(
+    agents: Query<Entity, (With<NavTarget>, Without<NavPath>)>,
+    mut commands: Commands,
+) {
+    for entity in agents.iter() {
+        commands
+            .entity(entity)
+            .insert((NavPath::default(), Avoidance::default()));
+    }
+}
+
+fn track_nav_mesh_changes(
+    time: Res<Time>,
+    nav_mesh_settings: Res<NavMeshSettings>,
+    changed_affectors: Query<
+        (&Collider, &GlobalTransform),
+        (
+            With<NavMeshAffector>,
+            Or<(
+                Added<NavMeshAffector>,
+                Changed<GlobalTransform>,
+                Changed<Collider>,
+            )>,
+        ),
+    >,
+    mut removed_affectors: RemovedComponents<NavMeshAffector>,
+    mut changes: ResMut<NavMeshChanges>,
+) {
+    let now = time.elapsed_seconds();
+    for (collider, transform) in changed_affectors.iter() {
+        let aabb = collider
+            .raw
+            .compute_aabb(&utils::transform_to_iso(&transform.compute_transform()));
+        let min =
+            nav_mesh_settings.get_tile_containing_position(Vec2::new(aabb.mins.x, aabb.mins.z));
+        let max =
+            nav_mesh_settings.get_tile_containing_position(Vec2::new(aabb.maxs.x, aabb.maxs.z));
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                changes.tiles.insert(UVec2::new(x, y), now);
+            }
+        }
+    }
+    if removed_affectors.read().count() > 0 {
+        changes.everything_changed_at = Some(now);
+    }
+}
+
+/// Starts path queries for the agents whose path is missing or outdated, oldest paths first.
+fn request_paths(
+    time: Res<Time>,
+    mut agents: Query<(
+        &Transform,
+        &NavTarget,
+        &mut NavPath,
+        Option<&NavAgentProfile>,
+    )>,
+    links: Query<(&OffMeshLink, &GlobalTransform)>,
+    nav_meshes: Res<NavMeshes>,
+    changes: Res<NavMeshChanges>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("request_paths").entered();
+    let now = time.elapsed_seconds();
+    let mut due = Vec::new();
+    for (transform, nav_target, mut path, profile) in &mut agents {
+        let Some(destination) = nav_target.position else {
+            if path.destination.is_some() || path.request.is_some() {
+                *path = NavPath::default();
+            }
+            continue;
+        };
+        let from = transform.translation;
+        // Don't abandon a jump or climb halfway
+        if path.request.is_some()
+            || path.link_started_at.is_some()
+            || (destination - from).length_squared() < nav_target.stop_distance.squared()
+        {
+            continue;
+        }
+        let target_moved = path.destination.map_or(true, |planned| {
+            planned.distance(destination) > REPLAN_DISTANCE
+        });
+        let needs_plan = match path.retry_at {
+            Some(retry_at) => target_moved || now >= retry_at,
+            None => target_moved || changes.invalidates(&path, now),
+        };
+        if needs_plan {
+            due.push((
+                path,
+                from,
+                destination,
+                profile.copied().unwrap_or_default(),
+            ));
+        }
+    }
+    due.sort_by(|(a, ..), (b, ..)| a.planned_at.total_cmp(&b.planned_at));
+    let links: Vec<_> = links
+        .iter()
+        .flat_map(|(link, transform)| link.nav_links(transform))
+        .collect();
+    let task_pool = AsyncComputeTaskPool::get();
+    for (mut path, from, destination, profile) in due.into_iter().take(MAX_PATH_REQUESTS_PER_FRAME)
+    {
+        let (nav_mesh, nav_mesh_settings) = nav_meshes.get(profile);
+        let task = task_pool.spawn(find_path_async(
+            nav_mesh,
+            nav_mesh_settings,
+            from,
+            destination,
+            links.clone(),
+        ));
+        path.request = Some(PathRequest { destination, task });
+    }
+}
+
+async fn find_path_async(
+    nav_mesh: Arc<RwLock<NavMeshTiles>>,
+    nav_mesh_settings: NavMeshSettings,
+    from: Vec3,
+    to: Vec3,
+    links: Vec<NavLink>,
+) -> Option<Vec<Waypoint>> {
+    let nav_mesh = nav_mesh.read().ok()?;
+    plan_route(from, to, &links, |from, to| {
+        find_path(&nav_mesh, &nav_mesh_settings, from, to, None, None).ok()
+    })
+}
+
+fn receive_paths(
     #[cfg(feature = "dev")] mut commands: Commands,
-    mut with_follower: Query<(&Transform, &mut Walk), (With<Npc>, Without<Player>)>,
-    with_player: Query<&Transform, (With<Player>, Without<Npc>)>,
+    time: Res<Time>,
     nav_mesh_settings: Res<NavMeshSettings>,
-    nav_mesh: Res<NavMesh>,
-    #[cfg(feature = "dev")] editor_state: Res<bevy_editor_pls::editor::Editor>,
+    mut agents: Query<&mut NavPath>,
+    #[cfg(feature = "dev")] editor_state: Option<Res<bevy_editor_pls::editor::Editor>>,
 ) -> anyhow::Result<()> {
+    let now = time.elapsed_seconds();
+    for mut path in &mut agents {
+        let Some(request) = path.request.as_mut() else {
+            continue;
+        };
+        let Some(waypoints) = future::block_on(future::poll_once(&mut request.task)) else {
+            continue;
+        };
+        let destination = request.destination;
+        path.request = None;
+        path.destination = Some(destination);
+        path.planned_at = now;
+        path.next = 0;
+        path.link_started_at = None;
+        path.jump_released = false;
+        let Some(waypoints) = waypoints else {
+            path.waypoints.clear();
+            path.tiles.clear();
+            path.retry_at = Some(now + RETRY_DELAY);
+            continue;
+        };
+        path.retry_at = None;
+        path.tiles = waypoints
+            .iter()
+            .map(|waypoint| nav_mesh_settings.get_tile_containing_position(waypoint.position.xz()))
+            .collect();
+        path.tiles.dedup();
+        #[cfg(feature = "dev")]
+        if let Some(editor_state) = editor_state.as_ref() {
+            let nav_render_enabled = editor_state
+                .window_state::<DevEditorWindow>()
+                .context("Failed to read dev window state")?
+                .navmesh_render_enabled;
+            if nav_render_enabled {
+                let shifted_path = waypoints
+                    .iter()
+                    .map(|waypoint| waypoint.position + Vec3::new(0., 0.2, 0.))
+                    .collect::<Vec<_>>();
+                commands.spawn(DrawPath {
+                    timer: Some(Timer::from_seconds(4.0, TimerMode::Once)),
+                    pulled_path: shifted_path,
+                    color: Color::BLUE,
+                });
+            }
+        }
+        path.waypoints = waypoints;
+    }
+    Ok(())
+}
+
+/// Walks agents along their cached path. Once it is used up, they walk straight towards the target,
+/// which moved less than [`REPLAN_DISTANCE`] from the end of the path.
+/// Off-mesh links on the way are traversed by jumping, walking off the ledge or climbing.
+fn follow_paths(
+    time: Res<Time>,
+    mut agents: Query<(
+        &Transform,
+        &Velocity,
+        &NavTarget,
+        &mut NavPath,
+        &mut Walk,
+        &mut Jump,
+        &mut Climb,
+    )>,
+) {
     #[cfg(feature = "tracing")]
-    let _span = info_span!("query_mesh").entered();
-    if let Ok(nav_mesh) = nav_mesh.get().read() {
-        for (follower_transform, mut walking) in &mut with_follower {
-            for player_transform in &with_player {
-                let from = follower_transform.translation;
-                let to = player_transform.translation;
-                if (to - from).length_squared() < 3.0f32.squared() {
-                    continue;
+    let _span = info_span!("follow_paths").entered();
+    let now = time.elapsed_seconds();
+    for (transform, velocity, nav_target, mut path, mut walking, mut jump, mut climb) in &mut agents
+    {
+        let Some(destination) = nav_target.position else {
+            continue;
+        };
+        let from = transform.translation;
+        if (destination - from).length_squared() < nav_target.stop_distance.squared()
+            || path.destination.is_none()
+            || path.retry_at.is_some()
+        {
+            continue;
+        }
+        while let Some(waypoint) = path.waypoints.get(path.next) {
+            if !waypoint.is_reached_from(from) {
+                break;
+            }
+            path.next += 1;
+            path.link_started_at = None;
+            path.jump_released = false;
+        }
+        let waypoint = path.waypoints.get(path.next).copied();
+        let goal = waypoint.map_or(destination, |waypoint| waypoint.position);
+        let mut to_goal = (goal - from).horizontal();
+        if let Some(traversal) = waypoint.and_then(|waypoint| waypoint.link) {
+            let started_at = *path.link_started_at.get_or_insert(now);
+            if now - started_at > LINK_TIMEOUT {
+                *path = NavPath::default();
+                continue;
+            }
+            match traversal {
+                LinkTraversal::Jump => {
+                    if !path.jump_released {
+                        jump.requested = true;
+                        // Hold the jump until the apex or until high enough to land on the end
+                        path.jump_released =
+                            velocity.linvel.y < -1. || from.y > goal.y + JUMP_CLEARANCE;
+                    }
                 }
-
-                if let Ok(path) =
-                    find_polygon_path(&nav_mesh, &nav_mesh_settings, from, to, None, None)
-                {
-                    let path = perform_string_pulling_on_path(&nav_mesh, from, to, &path)
-                        .map_err(|e| anyhow::Error::msg(format!("{e:?}")))?;
-                    #[cfg(feature = "dev")]
-                    {
-                        let nav_render_enabled = editor_state
-                            .window_state::<DevEditorWindow>()
-                            .context("Failed to read dev window state")?
-                            .navmesh_render_enabled;
-                        if nav_render_enabled {
-                            let shifted_path = path
-                                .iter()
-                                .map(|point| *point + Vec3::new(0., 0.2, 0.))
-                                .collect::<Vec<_>>();
-                            commands.spawn(DrawPath {
-                                timer: Some(Timer::from_seconds(4.0, TimerMode::Once)),
-                                pulled_path: shifted_path,
-                                color: Color::BLUE,
-                            });
-                        }
+                LinkTraversal::Drop => {}
+                LinkTraversal::Climb => {
+                    climb.mount_requested = true;
+                    if climb.climbing {
+                        // Walking towards the climbable climbs up, walking away from it climbs down.
+                        // The link leads towards the climbable when going up and away from it when going down.
+                        let link_start = path
+                            .next
+                            .checked_sub(1)
+                            .and_then(|previous| path.waypoints.get(previous))
+                            .map_or(from, |previous| previous.position);
+                        to_goal = (goal - link_start).horizontal();
                     }
-                    let dir = path
-                        .into_iter()
-                        .map(|next_point| (next_point - from).horizontal())
-                        .filter(|dir| dir.length_squared() > 1e-3f32.squared())
-                        .filter_map(|dir| dir.try_normalize())
-                        .next();
-                    walking.direction = dir;
                 }
             }
         }
+        if to_goal.length_squared() > 1e-3f32.squared() {
+            walking.direction = to_goal.try_normalize();
+        }
     }
-    Ok(())
 }
 
-#[cfg(feature = "dev")]
-fn draw_navmesh(
-    editor: Res<bevy_editor_pls::editor::Editor>,
-    mut draw_nav_mesh: ResMut<DrawNavMesh>,
-) -> anyhow::Result<()> {
-    let nav_render_enabled = editor
-        .window_state::<DevEditorWindow>()
-        .context("Failed to read dev window state")?
-        .navmesh_render_enabled;
-    draw_nav_mesh.0 = nav_render_enabled;
-    Ok(())
+/// Steers agents away from all other characters: apart when they are already too close,
+/// and sideways when they are about to bump into each other.
+/// Agents that are standing still only move when someone gets too close.
+fn avoid_crowds(
+    time: Res<Time>,
+    characters: Query<(Entity, &Transform, &Velocity, &AgentRadius)>,
+    mut agents: Query<(
+        Entity,
+        &Transform,
+        &Velocity,
+        &AgentRadius,
+        &mut Walk,
+        &mut Avoidance,
+    )>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("avoid_crowds").entered();
+    let characters: Vec<_> = characters
+        .iter()
+        .map(|(entity, transform, velocity, radius)| {
+            (
+                entity,
+                transform.translation,
+                velocity.linvel.horizontal(),
+                radius.0,
+            )
+        })
+        .collect();
+    let smoothing = (time.delta_seconds() * AVOIDANCE_RESPONSIVENESS).min(1.);
+    for (entity, transform, velocity, radius, mut walking, mut avoidance) in &mut agents {
+        let position = transform.translation;
+        let desired = walking.direction.unwrap_or_default();
+        let velocity = velocity.linvel.horizontal();
+        let mut separation = Vec3::ZERO;
+        let mut sidestep = Vec3::ZERO;
+        for &(other, other_position, other_velocity, other_radius) in &characters {
+            if other == entity {
+                continue;
+            }
+            let offset = (other_position - position).horizontal();
+            let distance = offset.length();
+            let contact_distance = radius.0 + other_radius;
+            let personal_space = contact_distance + AVOIDANCE_MARGIN;
+            if distance < personal_space {
+                // Agents on top of each other split up in opposite directions
+                let away = (-offset).try_normalize().unwrap_or(if entity < other {
+                    Vec3::X
+                } else {
+                    Vec3::NEG_X
+                });
+                separation += away * (1. - distance / personal_space);
+            }
+
+            if desired == Vec3::ZERO {
+                continue;
+            }
+            let relative_velocity = velocity - other_velocity;
+            let Some(approach_direction) = relative_velocity.try_normalize() else {
+                continue;
+            };
+            let closing_speed = relative_velocity.dot(offset.normalize_or_zero());
+            if closing_speed <= 0. {
+                continue;
+            }
+            let time_to_contact = (distance - contact_distance).max(0.) / closing_speed;
+            if time_to_contact > AVOIDANCE_LOOKAHEAD {
+                continue;
+            }
+            // How far the other one passes by the side if nobody changes course
+            let miss = offset - approach_direction * offset.dot(approach_direction);
+            if miss.length() > personal_space {
+                continue;
+            }
+            // Head-on, everyone passes on the same side, like in traffic
+            let side = (-miss)
+                .try_normalize()
+                .unwrap_or_else(|| desired.cross(Vec3::Y).normalize_or_zero());
+            sidestep += side * (1. - time_to_contact / AVOIDANCE_LOOKAHEAD);
+        }
+
+        let steering = separation * SEPARATION_WEIGHT + sidestep;
+        avoidance.0 = avoidance.0.lerp(steering, smoothing);
+        if desired != Vec3::ZERO {
+            let steered = (desired.normalize() + avoidance.0).normalize_or_zero();
+            walking.direction = Some(steered * desired.length());
+        } else if separation != Vec3::ZERO {
+            walking.direction = Some(avoidance.0.clamp_length_max(1.));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movement::behavior::Behavior;
+    use crate::movement::physics::CollisionLayers;
+    use crate::test_harness::{TestApp, TestEntity};
+    use bevy_rapier3d::prelude::RigidBody;
+
+    #[test]
+    fn npc_follows_player() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(30.));
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        let npc = app.spawn(TestEntity::npc().at(Vec3::new(10., 1., 10.)));
+
+        let caught_up = app.tick_until(64 * 20, |app| {
+            app.translation(npc).distance(app.translation(player)) < 4.
+        });
+
+        assert!(
+            caught_up.is_some(),
+            "NPC at {} never caught up to the player at {}",
+            app.translation(npc),
+            app.translation(player)
+        );
+
+        app.ticks(64);
+        let Behavior::Follow { distance, .. } = Behavior::default() else {
+            panic!("NPCs should follow by default");
+        };
+        let kept = app.translation(npc).distance(app.translation(player));
+        assert!(
+            kept > distance - 1. && kept < distance + 1.,
+            "NPC should keep a distance of {distance} to the player, but is {kept} away"
+        );
+    }
+
+    #[test]
+    fn path_requests_are_limited_per_frame() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(30.));
+        app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        for i in 0..10 {
+            app.spawn(TestEntity::npc().at(Vec3::new(i as f32 * 2. - 10., 1., 10.)));
+        }
+
+        let planning = |app: &mut TestApp| {
+            app.app
+                .world
+                .query::<&NavPath>()
+                .iter(&app.app.world)
+                .filter(|path| path.request.is_some() || path.destination.is_some())
+                .count()
+        };
+        let started = app.tick_until(64, |app| planning(app) > 0);
+
+        assert!(started.is_some(), "No NPC requested a path");
+        assert!(planning(&mut app) <= MAX_PATH_REQUESTS_PER_FRAME);
+    }
+
+    #[test]
+    fn only_small_npcs_fit_through_narrow_gap() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        // A wall along the x axis with a gap of 1.2 m in the middle
+        for x in [-10.6, 10.6] {
+            app.app.world.spawn((
+                TransformBundle::from_transform(Transform::from_xyz(x, 1.5, 0.)),
+                RigidBody::Fixed,
+                Collider::cuboid(10., 1.5, 0.25),
+                CollisionLayers::terrain(),
+                NavMeshAffector,
+            ));
+        }
+        let [small, humanoid] = [
+            (-2., NavAgentProfile::Small),
+            (2., NavAgentProfile::Humanoid),
+        ]
+        .map(|(x, profile)| {
+            app.spawn(TestEntity::npc().at(Vec3::new(x, 1., 5.)).with((
+                profile,
+                Behavior::GoTo {
+                    position: Vec3::new(x, 0., -5.),
+                },
+            )))
+        });
+
+        let arrived = app.tick_until(64 * 20, |app| app.translation(small).z < -4.);
+
+        assert!(
+            arrived.is_some(),
+            "Small NPC at {} never made it through the gap",
+            app.translation(small)
+        );
+        assert!(
+            app.translation(humanoid).z > 0.,
+            "Humanoid NPC at {} should not fit through the gap",
+            app.translation(humanoid)
+        );
+    }
+
+    #[test]
+    fn npcs_walking_towards_each_other_pass() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(30.));
+        let goals = [Vec3::new(6., 0., 0.), Vec3::new(-6., 0., 0.)];
+        let npcs = goals.map(|goal| {
+            app.spawn(
+                TestEntity::npc()
+                    .at(Vec3::new(-goal.x, 1., 0.))
+                    .with(Behavior::GoTo { position: goal }),
+            )
+        });
+
+        let arrived = app.tick_until(64 * 20, |app| {
+            npcs.iter()
+                .zip(goals)
+                .all(|(npc, goal)| (app.translation(*npc) - goal).horizontal().length() < 1.)
+        });
+
+        assert!(
+            arrived.is_some(),
+            "NPCs at {} and {} got stuck on each other",
+            app.translation(npcs[0]),
+            app.translation(npcs[1])
+        );
+    }
 }
@@ -0,0 +1,295 @@
+use crate::util::Vec3Ext;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+/// Horizontal distance at which an agent moves on to the next waypoint of its path
+const WAYPOINT_REACHED_DISTANCE: f32 = 0.3;
+/// How far above or below the end of a link an agent's center may be when it counts as arrived
+const LINK_END_HEIGHT_TOLERANCE: f32 = 1.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<OffMeshLink>()
+        .register_type::<LinkTraversal>();
+}
+
+/// A connection the navmesh doesn't know about, like a gap to jump over, a ledge to drop down or a ladder.
+/// Can be added to empties in Blender, which mark the start of the link.
+/// Agents take links when that is shorter than walking or when there is no other way.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct OffMeshLink {
+    /// Where the link leads, in the local space of the empty
+    #[reflect(default)]
+    pub(crate) end: Vec3,
+    /// Whether agents can also take the link from the end to the start. Drops are never taken upwards.
+    #[reflect(default)]
+    pub(crate) bidirectional: bool,
+    #[reflect(default)]
+    pub(crate) traversal: LinkTraversal,
+}
+
+/// How an agent gets from the start of an [`OffMeshLink`] to its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize, Default)]
+pub(crate) enum LinkTraversal {
+    /// Jumps towards the end, holding the jump until high enough to land there
+    #[default]
+    Jump,
+    /// Walks off the ledge towards the end
+    Drop,
+    /// Climbs the [`Climbable`](crate::movement::character_controller::Climbable) between start and end
+    Climb,
+}
+
+impl OffMeshLink {
+    /// The directions agents can take the link in, in world space.
+    pub(super) fn nav_links(&self, transform: &GlobalTransform) -> impl Iterator<Item = NavLink> {
+        let start = transform.translation();
+        let end = transform.transform_point(self.end);
+        let traversal = self.traversal;
+        let forward = NavLink {
+            start,
+            end,
+            traversal,
+        };
+        let backward = (self.bidirectional
+            && (traversal != LinkTraversal::Drop || end.y >= start.y))
+            .then_some(NavLink {
+                start: end,
+                end: start,
+                traversal,
+            });
+        iter::once(forward).chain(backward)
+    }
+}
+
+/// An [`OffMeshLink`] taken in one direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct NavLink {
+    pub(super) start: Vec3,
+    pub(super) end: Vec3,
+    pub(super) traversal: LinkTraversal,
+}
+
+/// A point of a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Waypoint {
+    pub(super) position: Vec3,
+    /// Set when the waypoint is the end of a link, which the agent has to traverse this way
+    pub(super) link: Option<LinkTraversal>,
+}
+
+impl Waypoint {
+    fn walked(position: Vec3) -> Self {
+        Self {
+            position,
+            link: None,
+        }
+    }
+
+    /// Link ends also have to be reached vertically, so that agents don't count as arrived halfway up a ladder.
+    pub(super) fn is_reached_from(&self, position: Vec3) -> bool {
+        let offset = self.position - position;
+        offset.horizontal().length() <= WAYPOINT_REACHED_DISTANCE
+            && (self.link.is_none() || offset.y.abs() <= LINK_END_HEIGHT_TOLERANCE)
+    }
+}
+
+/// Finds the shortest route from `from` to `to` that walks along the navmesh and takes any number of `links`.
+/// `walk` finds a path on the navmesh. As the routes between all link ends are considered,
+/// it is called up to (links + 1)² times, so keep the number of links in a level small.
+pub(super) fn plan_route(
+    from: Vec3,
+    to: Vec3,
+    links: &[NavLink],
+    mut walk: impl FnMut(Vec3, Vec3) -> Option<Vec<Vec3>>,
+) -> Option<Vec<Waypoint>> {
+    // Dijkstra over the places an agent can stand at: node 0 is `from`, node i + 1 is the end of link i
+    let positions: Vec<_> = iter::once(from)
+        .chain(links.iter().map(|link| link.end))
+        .collect();
+    let mut routes: Vec<Option<(f32, Vec<Waypoint>)>> = vec![None; positions.len()];
+    routes[0] = Some((0., Vec::new()));
+    let mut settled = vec![false; positions.len()];
+    let mut best: Option<(f32, Vec<Waypoint>)> = None;
+    loop {
+        let next = routes
+            .iter()
+            .enumerate()
+            .filter(|(node, _)| !settled[*node])
+            .filter_map(|(node, route)| route.as_ref().map(|(cost, _)| (node, *cost)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b));
+        let Some((node, cost)) = next else {
+            break;
+        };
+        if best
+            .as_ref()
+            .is_some_and(|(best_cost, _)| *best_cost <= cost)
+        {
+            break;
+        }
+        settled[node] = true;
+        let position = positions[node];
+        let route = routes[node].as_ref().map(|(_, route)| route.clone());
+        let extend = |route: Option<Vec<Waypoint>>, path: Vec<Vec3>| {
+            let mut route = route.unwrap_or_default();
+            route.extend(path.into_iter().map(Waypoint::walked));
+            route
+        };
+
+        if let Some(path) = walk(position, to) {
+            let total = cost + path_length(position, &path);
+            if best
+                .as_ref()
+                .map_or(true, |(best_cost, _)| total < *best_cost)
+            {
+                best = Some((total, extend(route.clone(), path)));
+            }
+        }
+        for (index, link) in links.iter().enumerate() {
+            let link_node = index + 1;
+            if settled[link_node] {
+                continue;
+            }
+            let Some(path) = walk(position, link.start) else {
+                continue;
+            };
+            let total = cost + path_length(position, &path) + link.start.distance(link.end);
+            if routes[link_node]
+                .as_ref()
+                .is_some_and(|(known_cost, _)| *known_cost <= total)
+            {
+                continue;
+            }
+            let mut link_route = extend(route.clone(), path);
+            link_route.push(Waypoint {
+                position: link.end,
+                link: Some(link.traversal),
+            });
+            routes[link_node] = Some((total, link_route));
+        }
+    }
+    best.map(|(_, route)| route)
+}
+
+fn path_length(from: Vec3, path: &[Vec3]) -> f32 {
+    iter::once(from)
+        .chain(path.iter().copied())
+        .zip(path.iter().copied())
+        .map(|(a, b)| a.distance(b))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movement::behavior::Behavior;
+    use crate::movement::physics::CollisionLayers;
+    use crate::test_harness::{TestApp, TestEntity};
+    use bevy_rapier3d::prelude::{Collider, RigidBody};
+    use oxidized_navigation::NavMeshAffector;
+
+    /// Walking works on either side of the x = 0 plane, but not across it
+    fn walk_with_gap(from: Vec3, to: Vec3) -> Option<Vec<Vec3>> {
+        (from.x.signum() == to.x.signum()).then(|| vec![to])
+    }
+
+    fn jump(start: Vec3, end: Vec3) -> NavLink {
+        NavLink {
+            start,
+            end,
+            traversal: LinkTraversal::Jump,
+        }
+    }
+
+    #[test]
+    fn route_takes_link_across_gap() {
+        let links = [jump(Vec3::new(-1., 0., 0.), Vec3::new(1., 0., 0.))];
+
+        let route = plan_route(
+            Vec3::new(-5., 0., 0.),
+            Vec3::new(5., 0., 0.),
+            &links,
+            walk_with_gap,
+        );
+
+        let route = route.expect("No route across the gap");
+        let positions: Vec<_> = route.iter().map(|waypoint| waypoint.position).collect();
+        assert_eq!(
+            positions,
+            [
+                Vec3::new(-1., 0., 0.),
+                Vec3::new(1., 0., 0.),
+                Vec3::new(5., 0., 0.)
+            ]
+        );
+        assert_eq!(route[1].link, Some(LinkTraversal::Jump));
+    }
+
+    #[test]
+    fn route_walks_when_shorter_than_link() {
+        let links = [jump(Vec3::new(-1., 0., 0.), Vec3::new(-10., 0., 0.))];
+
+        let route = plan_route(
+            Vec3::new(-5., 0., 0.),
+            Vec3::new(-4., 0., 0.),
+            &links,
+            walk_with_gap,
+        );
+
+        assert_eq!(route, Some(vec![Waypoint::walked(Vec3::new(-4., 0., 0.))]));
+    }
+
+    #[test]
+    fn drops_are_not_taken_upwards() {
+        let link = OffMeshLink {
+            end: Vec3::new(0., -3., 2.),
+            bidirectional: true,
+            traversal: LinkTraversal::Drop,
+        };
+
+        let links: Vec<_> = link.nav_links(&GlobalTransform::default()).collect();
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].end, Vec3::new(0., -3., 2.));
+    }
+
+    #[test]
+    fn npc_drops_down_ledge() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        app.app.world.spawn((
+            TransformBundle::from_transform(Transform::from_xyz(0., 1., 0.)),
+            RigidBody::Fixed,
+            Collider::cuboid(3., 1., 3.),
+            CollisionLayers::terrain(),
+            NavMeshAffector,
+        ));
+        app.app.world.spawn((
+            OffMeshLink {
+                end: Vec3::new(3., -2., 0.),
+                bidirectional: false,
+                traversal: LinkTraversal::Drop,
+            },
+            TransformBundle::from_transform(Transform::from_xyz(2., 2., 0.)),
+        ));
+        let goal = Vec3::new(8., 0., 0.);
+        let npc = app.spawn(
+            TestEntity::npc()
+                .at(Vec3::new(0., 3., 0.))
+                .with(Behavior::GoTo { position: goal }),
+        );
+
+        let arrived = app.tick_until(64 * 20, |app| {
+            (app.translation(npc) - goal).horizontal().length() < 1.
+        });
+
+        assert!(
+            arrived.is_some(),
+            "NPC at {} never made it down the ledge",
+            app.translation(npc)
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::level_instantiation::on_spawn::player;
+use bevy::{
+    app::{AppLabel, SubApp},
+    prelude::*,
+    time::TimePlugin,
+    utils::{HashMap, HashSet},
+};
+use bevy_rapier3d::prelude::Collider;
+use oxidized_navigation::{
+    tiles::NavMeshTiles, NavMesh, NavMeshAffector, NavMeshSettings, OxidizedNavigationPlugin,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+
+/// Manually tweaked
+const CELL_WIDTH: f32 = 0.4 * player::RADIUS;
+
+/// Generates a navmesh per [`NavAgentProfile`]. oxidized_navigation only supports one navmesh per world,
+/// so the humanoid navmesh is generated in the main world and every other profile gets a sub app
+/// that mirrors the colliders affecting the navmesh and generates its own from them.
+/// Must be added after the main world's [`OxidizedNavigationPlugin`].
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<NavAgentProfile>();
+    let mut nav_meshes = HashMap::new();
+    nav_meshes.insert(
+        NavAgentProfile::Humanoid,
+        (
+            app.world.resource::<NavMesh>().get(),
+            NavAgentProfile::Humanoid.nav_mesh_settings(),
+        ),
+    );
+    for profile in [NavAgentProfile::Small, NavAgentProfile::Large] {
+        let mut sub_app = App::new();
+        sub_app
+            .add_plugins((
+                TimePlugin,
+                OxidizedNavigationPlugin::<Collider>::new(profile.nav_mesh_settings()),
+            ))
+            .init_resource::<MirroredAffectors>();
+        nav_meshes.insert(
+            profile,
+            (
+                sub_app.world.resource::<NavMesh>().get(),
+                profile.nav_mesh_settings(),
+            ),
+        );
+        app.insert_sub_app(NavMeshApp(profile), SubApp::new(sub_app, mirror_affectors));
+    }
+    app.insert_resource(NavMeshes(nav_meshes));
+}
+
+/// The size class of an NPC. Every profile paths on its own navmesh,
+/// so that small characters squeeze through gaps that large ones would get stuck in.
+/// Can be added to NPCs in Blender. NPCs without one use [`NavAgentProfile::Humanoid`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Component, Reflect, Serialize, Deserialize, Default,
+)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+pub(crate) enum NavAgentProfile {
+    /// About half as tall and wide as the player
+    Small,
+    /// Sized like the player
+    #[default]
+    Humanoid,
+    /// About twice as tall and wide as the player
+    Large,
+}
+
+impl NavAgentProfile {
+    /// All profiles share the cell and tile sizes, so tile coordinates are the same on every navmesh.
+    pub(super) fn nav_mesh_settings(self) -> NavMeshSettings {
+        // In cells, manually tweaked
+        let (walkable_height, walkable_radius, step_height) = match self {
+            Self::Small => (12, 2, 2),
+            Self::Humanoid => (25, 4, 3),
+            Self::Large => (50, 8, 5),
+        };
+        // consts manually tweaked
+        NavMeshSettings {
+            cell_width: CELL_WIDTH,
+            cell_height: 0.5 * CELL_WIDTH,
+            tile_width: 170,
+            world_half_extents: 250.0,
+            world_bottom_bound: -20.0,
+            max_traversable_slope_radians: (40.0_f32 - 0.1).to_radians(),
+            walkable_height,
+            walkable_radius,
+            step_height,
+            min_region_area: 30,
+            merge_region_area: 500,
+            max_contour_simplification_error: 1.3,
+            max_edge_length: 100,
+            max_tile_generation_tasks: None,
+        }
+    }
+}
+
+/// The navmeshes of all profiles together with the settings they were generated with.
+#[derive(Resource)]
+pub(super) struct NavMeshes(HashMap<NavAgentProfile, (Arc<RwLock<NavMeshTiles>>, NavMeshSettings)>);
+
+impl NavMeshes {
+    pub(super) fn get(
+        &self,
+        profile: NavAgentProfile,
+    ) -> (Arc<RwLock<NavMeshTiles>>, NavMeshSettings) {
+        let (nav_mesh, settings) = &self.0[&profile];
+        (nav_mesh.clone(), settings.clone())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, AppLabel)]
+struct NavMeshApp(NavAgentProfile);
+
+/// Maps the navmesh affectors of the main world to their stand-ins in a sub app.
+#[derive(Debug, Resource, Default)]
+struct MirroredAffectors(HashMap<Entity, (Entity, GlobalTransform)>);
+
+/// Keeps the colliders of a profile's sub app in sync with the navmesh affectors of the main world.
+/// Only changed affectors are written, so that oxidized_navigation only rebuilds the tiles they are in.
+fn mirror_affectors(main_world: &mut World, sub_app: &mut App) {
+    let mut affectors = main_world
+        .query_filtered::<(Entity, Ref<Collider>, &GlobalTransform), With<NavMeshAffector>>();
+    sub_app
+        .world
+        .resource_scope(|world, mut mirrored: Mut<MirroredAffectors>| {
+            let mut present = HashSet::new();
+            for (entity, collider, transform) in affectors.iter(main_world) {
+                present.insert(entity);
+                match mirrored.0.get_mut(&entity) {
+                    Some((mirror, mirrored_transform))
+                        if *mirrored_transform != *transform || collider.is_changed() =>
+                    {
+                        world
+                            .entity_mut(*mirror)
+                            .insert((Collider::clone(&collider), *transform));
+                        *mirrored_transform = *transform;
+                    }
+                    Some(_) => {}
+                    None => {
+                        let mirror = world
+                            .spawn((Collider::clone(&collider), *transform, NavMeshAffector))
+                            .id();
+                        mirrored.0.insert(entity, (mirror, *transform));
+                    }
+                }
+            }
+            mirrored.0.retain(|entity, (mirror, _)| {
+                let still_present = present.contains(entity);
+                if !still_present {
+                    world.despawn(*mirror);
+                }
+                still_present
+            });
+        });
+}
@@ -2,10 +2,18 @@ use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
 // use bevy_xpbd_3d::prelude::*;
 
+mod collision_layer;
+mod interpolation;
+
+pub(crate) use collision_layer::{CollisionLayer, CollisionLayers, LayerMask};
+
 /// Sets up and configures the XPBD physics.
+/// Rendered transforms are smoothed between physics ticks by [`interpolation::plugin`].
+/// Collision groups are assigned through named layers by [`collision_layer::plugin`].
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins(RapierPhysicsPlugin::<NoUserData>::default().with_default_system_setup(false))
         .add_plugins(crate::physics_time::TimePlugin)
+        .add_plugins((interpolation::plugin, collision_layer::plugin))
         .add_systems(
             crate::physics_time::PhysicsSchedule,
             (
@@ -20,13 +28,3 @@ pub(super) fn plugin(app: &mut App) {
     // Using the default fixed timestep causes issues on faster (165 Hz) machines.
     //  .insert_resource(Time::new_with(Physics::variable(1.0 / 60.)));
 }
-
-// remain here as reference.
-// #[derive(PhysicsLayer)]
-// pub(crate) enum CollisionLayer {
-//     Player,1
-//     Character,2
-//     Terrain,3
-//     CameraObstacle,4
-//     Sensor,5
-// }
@@ -0,0 +1,249 @@
+use crate::physics_time::PhysicsSchedule;
+use anyhow::bail;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::ops::{BitOr, BitOrAssign};
+
+/// Turns [`CollisionLayers`] into rapier [`CollisionGroups`] right before they are synced to rapier,
+/// warning about layers that can't be right.
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<CollisionLayer>()
+        .register_type::<CollisionLayers>()
+        .add_systems(
+            PhysicsSchedule,
+            apply_collision_layers.before(PhysicsSet::SyncBackend),
+        );
+}
+
+/// Named collision layer, backed by a single rapier [`Group`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Reflect, Serialize, Deserialize)]
+#[reflect(Serialize, Deserialize)]
+pub(crate) enum CollisionLayer {
+    Player,
+    Character,
+    Terrain,
+    CameraObstacle,
+    Sensor,
+    /// Dynamic rigid bodies that characters can push around
+    Prop,
+}
+
+impl CollisionLayer {
+    pub(crate) const ALL: [Self; 6] = [
+        Self::Player,
+        Self::Character,
+        Self::Terrain,
+        Self::CameraObstacle,
+        Self::Sensor,
+        Self::Prop,
+    ];
+
+    pub(crate) fn group(self) -> Group {
+        match self {
+            Self::Player => Group::GROUP_1,
+            Self::Character => Group::GROUP_2,
+            Self::Terrain => Group::GROUP_3,
+            Self::CameraObstacle => Group::GROUP_4,
+            Self::Sensor => Group::GROUP_5,
+            Self::Prop => Group::GROUP_6,
+        }
+    }
+}
+
+/// A set of [`CollisionLayer`]s, built with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub(crate) struct LayerMask(u32);
+
+impl LayerMask {
+    pub(crate) const NONE: Self = Self(0);
+
+    pub(crate) fn contains(self, layer: CollisionLayer) -> bool {
+        self.0 & layer.group().bits() != 0
+    }
+
+    pub(crate) fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub(crate) fn iter(self) -> impl Iterator<Item = CollisionLayer> {
+        CollisionLayer::ALL
+            .into_iter()
+            .filter(move |layer| self.contains(*layer))
+    }
+
+    pub(crate) fn group(self) -> Group {
+        Group::from_bits_truncate(self.0)
+    }
+}
+
+impl From<CollisionLayer> for LayerMask {
+    fn from(layer: CollisionLayer) -> Self {
+        Self(layer.group().bits())
+    }
+}
+
+impl FromIterator<CollisionLayer> for LayerMask {
+    fn from_iter<T: IntoIterator<Item = CollisionLayer>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::NONE, |mask, layer| mask | layer)
+    }
+}
+
+impl BitOr for CollisionLayer {
+    type Output = LayerMask;
+
+    fn bitor(self, rhs: Self) -> LayerMask {
+        LayerMask::from(self) | rhs
+    }
+}
+
+impl BitOr<CollisionLayer> for LayerMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: CollisionLayer) -> Self {
+        Self(self.0 | LayerMask::from(rhs).0)
+    }
+}
+
+impl BitOrAssign<CollisionLayer> for LayerMask {
+    fn bitor_assign(&mut self, rhs: CollisionLayer) {
+        *self = *self | rhs;
+    }
+}
+
+/// The collision layers of a collider. Two colliders only interact if each one's memberships
+/// contain at least one of the other one's filters.
+/// Can be added in Blender, where it replaces the layers a collider would get from code.
+#[derive(Debug, Clone, PartialEq, Eq, Component, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize)]
+pub(crate) struct CollisionLayers {
+    /// Layers this collider is part of
+    pub(crate) memberships: Vec<CollisionLayer>,
+    /// Layers this collider interacts with
+    pub(crate) filters: Vec<CollisionLayer>,
+}
+
+impl CollisionLayers {
+    pub(crate) fn new(memberships: impl Into<LayerMask>, filters: impl Into<LayerMask>) -> Self {
+        Self {
+            memberships: memberships.into().iter().collect(),
+            filters: filters.into().iter().collect(),
+        }
+    }
+
+    /// Static level geometry
+    pub(crate) fn terrain() -> Self {
+        Self::new(
+            CollisionLayer::Terrain | CollisionLayer::CameraObstacle,
+            CollisionLayer::Character | CollisionLayer::Prop,
+        )
+    }
+
+    /// Dynamic rigid bodies placed in the level
+    pub(crate) fn prop() -> Self {
+        Self::new(
+            CollisionLayer::Prop,
+            CollisionLayer::Character | CollisionLayer::Terrain | CollisionLayer::Prop,
+        )
+    }
+
+    pub(crate) fn memberships(&self) -> LayerMask {
+        self.memberships.iter().copied().collect()
+    }
+
+    pub(crate) fn filters(&self) -> LayerMask {
+        self.filters.iter().copied().collect()
+    }
+
+    pub(crate) fn add_membership(&mut self, layer: CollisionLayer) {
+        if !self.memberships.contains(&layer) {
+            self.memberships.push(layer);
+        }
+    }
+
+    fn validate(&self, is_sensor: bool) -> anyhow::Result<()> {
+        let memberships = self.memberships();
+        if memberships.is_empty() {
+            bail!("Collider is not part of any layer, so nothing can collide with it");
+        }
+        if self.filters().is_empty() {
+            bail!("Collider has no filters, so it can't collide with anything");
+        }
+        if memberships.iter().count() != self.memberships.len()
+            || self.filters().iter().count() != self.filters.len()
+        {
+            bail!("Collision layers contain duplicates");
+        }
+        if memberships.contains(CollisionLayer::Sensor) != is_sensor {
+            bail!("Only sensors should be part of the sensor layer");
+        }
+        Ok(())
+    }
+}
+
+impl From<&CollisionLayers> for CollisionGroups {
+    fn from(layers: &CollisionLayers) -> Self {
+        CollisionGroups::new(layers.memberships().group(), layers.filters().group())
+    }
+}
+
+fn apply_collision_layers(
+    mut commands: Commands,
+    colliders: Query<
+        (Entity, &CollisionLayers, Has<Sensor>, Option<&Name>),
+        Changed<CollisionLayers>,
+    >,
+) {
+    for (entity, layers, is_sensor, name) in colliders.iter() {
+        if let Err(e) = layers.validate(is_sensor) {
+            let name = name.map(|name| name.as_str()).unwrap_or("unnamed");
+            warn!("Invalid collision layers on {entity:?} ({name}): {e}");
+        }
+        commands
+            .entity(entity)
+            .insert(CollisionGroups::from(layers));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_convert_to_rapier_groups() {
+        let layers = CollisionLayers::new(
+            CollisionLayer::Terrain | CollisionLayer::CameraObstacle,
+            CollisionLayer::Character,
+        );
+        let groups = CollisionGroups::from(&layers);
+        assert_eq!(groups.memberships, Group::GROUP_3 | Group::GROUP_4);
+        assert_eq!(groups.filters, Group::GROUP_2);
+    }
+
+    #[test]
+    fn props_collide_with_terrain_and_characters() {
+        let interacts = |a: &CollisionLayers, b: &CollisionLayers| {
+            CollisionGroups::from(a).test(CollisionGroups::from(b))
+        };
+        let prop = CollisionLayers::prop();
+        assert!(interacts(&prop, &CollisionLayers::terrain()));
+        assert!(interacts(&prop, &prop));
+    }
+
+    #[test]
+    fn validation_rejects_inconsistent_layers() {
+        let sensor = CollisionLayers::new(CollisionLayer::Sensor, CollisionLayer::Player);
+        assert!(sensor.validate(true).is_ok());
+        assert!(sensor.validate(false).is_err());
+
+        let no_memberships = CollisionLayers::new(LayerMask::NONE, CollisionLayer::Player);
+        assert!(no_memberships.validate(false).is_err());
+
+        let duplicates = CollisionLayers {
+            memberships: vec![CollisionLayer::Terrain, CollisionLayer::Terrain],
+            filters: vec![CollisionLayer::Character],
+        };
+        assert!(duplicates.validate(false).is_err());
+    }
+}
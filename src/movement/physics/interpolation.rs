@@ -0,0 +1,278 @@
+use crate::physics_time::{run_physics_schedule, PhysicsSchedule, PhysicsTime, PhysicsTimeMode};
+use crate::system_set::GameSystemSet;
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Smooths the rendered transforms of rigid bodies between physics ticks.
+/// The physics simulation runs at a fixed tick rate in the [`PhysicsSchedule`], which is usually lower than
+/// the display rate. Without interpolation, bodies only move on frames in which a tick happened, which is visible as stutter.
+///
+/// Outside of the [`PostUpdate`] schedule, the [`Transform`] of an interpolated body always holds its physics pose,
+/// so gameplay code can read and write it as usual. Right before the camera is updated, it is replaced by the
+/// rendered pose, which is restored to the physics pose at the start of the next frame.
+/// Writing to the [`Transform`] teleports the body without interpolation.
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<RenderInterpolation>()
+        .register_type::<NoRenderInterpolation>()
+        .register_type::<RenderInterpolationState>()
+        .add_systems(
+            PreUpdate,
+            (add_render_interpolation, restore_physics_transforms)
+                .chain()
+                .before(run_physics_schedule),
+        )
+        .add_systems(
+            PhysicsSchedule,
+            update_physics_poses.after(PhysicsSet::Writeback),
+        )
+        .add_systems(
+            PostUpdate,
+            interpolate_transforms.in_set(GameSystemSet::RenderInterpolation),
+        );
+}
+
+/// How the rendered transform of a rigid body is computed between physics ticks.
+/// Inserted automatically on all non-fixed rigid bodies that don't have a [`NoRenderInterpolation`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Component, Reflect, Serialize, Deserialize, Default,
+)]
+#[reflect(Component, Serialize, Deserialize)]
+pub(crate) enum RenderInterpolation {
+    /// Blends between the last two physics poses. Always correct, but lags one tick behind.
+    #[default]
+    Interpolate,
+    /// Predicts the pose from the last physics pose and the [`Velocity`]. No lag, but overshoots on sudden stops.
+    Extrapolate,
+}
+
+/// Opts a rigid body out of render interpolation, so that it is always rendered at its physics pose.
+#[derive(Debug, Clone, Eq, PartialEq, Component, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Component, Serialize, Deserialize)]
+pub(crate) struct NoRenderInterpolation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+struct RenderInterpolationState {
+    /// Physics pose before the last tick
+    previous: Transform,
+    /// Physics pose after the last tick
+    current: Transform,
+    /// Pose written in [`PostUpdate`], if any
+    rendered: Option<Transform>,
+}
+
+impl RenderInterpolationState {
+    fn new(transform: Transform) -> Self {
+        Self {
+            previous: transform,
+            current: transform,
+            rendered: None,
+        }
+    }
+
+    fn teleport(&mut self, transform: Transform) {
+        *self = Self::new(transform);
+    }
+}
+
+fn add_render_interpolation(
+    mut commands: Commands,
+    bodies: Query<
+        (Entity, &RigidBody, &Transform, Option<&RenderInterpolation>),
+        (
+            Without<RenderInterpolationState>,
+            Without<NoRenderInterpolation>,
+        ),
+    >,
+) {
+    for (entity, rigid_body, transform, interpolation) in bodies.iter() {
+        if *rigid_body == RigidBody::Fixed {
+            continue;
+        }
+        commands.entity(entity).insert((
+            interpolation.copied().unwrap_or_default(),
+            RenderInterpolationState::new(*transform),
+        ));
+    }
+}
+
+/// Puts the physics pose back into the [`Transform`] so that gameplay code and rapier never see the rendered pose.
+/// The [`GlobalTransform`] is restored as well, because rapier treats any change to it as a teleport.
+fn restore_physics_transforms(
+    mut bodies: Query<
+        (
+            &mut Transform,
+            &mut GlobalTransform,
+            &mut RenderInterpolationState,
+            Option<&Parent>,
+        ),
+        Without<NoRenderInterpolation>,
+    >,
+    parents: Query<&GlobalTransform, Without<RenderInterpolationState>>,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("restore_physics_transforms").entered();
+    for (mut transform, mut global_transform, mut state, parent) in bodies.iter_mut() {
+        let Some(rendered) = state.rendered.take() else {
+            continue;
+        };
+        if *transform != rendered {
+            // Something moved the body after it was rendered
+            state.teleport(*transform);
+            continue;
+        }
+        *transform = state.current;
+        // Same computation as rapier's writeback and bevy's transform propagation, so that the values are bit-identical
+        *global_transform = match parent.and_then(|parent| parents.get(parent.get()).ok()) {
+            Some(parent_global_transform) => parent_global_transform.mul_transform(*transform),
+            None => GlobalTransform::from(*transform),
+        };
+    }
+}
+
+fn update_physics_poses(
+    mut bodies: Query<(&Transform, &mut RenderInterpolationState), Without<NoRenderInterpolation>>,
+) {
+    for (transform, mut state) in bodies.iter_mut() {
+        state.previous = state.current;
+        state.current = *transform;
+    }
+}
+
+fn interpolate_transforms(
+    time: Res<PhysicsTime>,
+    mut bodies: Query<
+        (
+            &mut Transform,
+            &mut RenderInterpolationState,
+            &RenderInterpolation,
+            Option<&Velocity>,
+        ),
+        Without<NoRenderInterpolation>,
+    >,
+) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("interpolate_transforms").entered();
+    let context = time.context();
+    let overstep = match context.mode {
+        PhysicsTimeMode::Running { .. } => context.overstep.min(context.timestep),
+        // Show the exact result of the last tick when inspecting the simulation
+        PhysicsTimeMode::Paused | PhysicsTimeMode::Stepping { .. } => context.timestep,
+    };
+    let alpha = if context.timestep.is_zero() {
+        1.
+    } else {
+        overstep.as_secs_f32() / context.timestep.as_secs_f32()
+    };
+
+    for (mut transform, mut state, interpolation, velocity) in bodies.iter_mut() {
+        if *transform != state.current {
+            // Gameplay code moved the body since the last tick
+            state.teleport(*transform);
+            continue;
+        }
+        let rendered = match (interpolation, velocity) {
+            (RenderInterpolation::Extrapolate, Some(velocity)) if alpha < 1. => {
+                extrapolate(&state.current, velocity, overstep.as_secs_f32())
+            }
+            _ => interpolate(&state.previous, &state.current, alpha),
+        };
+        *transform = rendered;
+        state.rendered = Some(rendered);
+    }
+}
+
+fn interpolate(previous: &Transform, current: &Transform, alpha: f32) -> Transform {
+    Transform {
+        translation: previous.translation.lerp(current.translation, alpha),
+        rotation: previous.rotation.slerp(current.rotation, alpha),
+        scale: current.scale,
+    }
+}
+
+fn extrapolate(current: &Transform, velocity: &Velocity, seconds: f32) -> Transform {
+    let rotation = Quat::from_scaled_axis(velocity.angvel * seconds) * current.rotation;
+    Transform {
+        translation: current.translation + velocity.linvel * seconds,
+        rotation: rotation.normalize(),
+        scale: current.scale,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics_time::{PhysicsTimeExt, DEFAULT_TIMESTEP};
+    use crate::test_harness::TestApp;
+    use bevy::time::TimeUpdateStrategy;
+
+    #[test]
+    fn rendered_pose_lies_between_physics_poses() {
+        let mut app = TestApp::new();
+        let body = app
+            .app
+            .world
+            .spawn((
+                TransformBundle::from_transform(Transform::from_xyz(0., 10., 0.)),
+                RigidBody::Dynamic,
+                Collider::ball(0.5),
+                Velocity::linear(Vec3::new(2., 0., 0.)),
+            ))
+            .id();
+        app.ticks(4);
+        let physics_poses = *app.app.world.get::<RenderInterpolationState>(body).unwrap();
+        let (previous, current) = (
+            physics_poses.previous.translation,
+            physics_poses.current.translation,
+        );
+        assert_ne!(previous, current, "The body should be moving");
+
+        // Half a tick passes, which is not enough to run one
+        app.app
+            .insert_resource(TimeUpdateStrategy::ManualDuration(DEFAULT_TIMESTEP / 2));
+        app.app.world.resource_mut::<PhysicsTime>().run(1.);
+        app.app.update();
+
+        let rendered = app.app.world.get::<Transform>(body).unwrap().translation;
+        let halfway = previous.lerp(current, 0.5);
+        assert!(
+            rendered.distance(halfway) < 1e-4,
+            "Rendered at {rendered}, but the physics poses are {previous} and {current}"
+        );
+
+        // The start of the next frame puts back exactly the pose rapier knows
+        app.app.world.resource_mut::<PhysicsTime>().pause();
+        app.app.world.run_schedule(PreUpdate);
+        let transform = *app.app.world.get::<Transform>(body).unwrap();
+        let global_transform = *app.app.world.get::<GlobalTransform>(body).unwrap();
+        assert_eq!(transform, physics_poses.current);
+        let handle = app.app.world.get::<RapierRigidBodyHandle>(body).unwrap().0;
+        let rapier_translation = *app
+            .app
+            .world
+            .resource::<RapierContext>()
+            .bodies
+            .get(handle)
+            .unwrap()
+            .translation();
+        let rapier_translation = Vec3::new(
+            rapier_translation.x,
+            rapier_translation.y,
+            rapier_translation.z,
+        );
+        assert!(
+            global_transform.translation().distance(rapier_translation) < 1e-5,
+            "Restored {} but rapier has the body at {rapier_translation}",
+            global_transform.translation()
+        );
+        assert_eq!(
+            app.app
+                .world
+                .get::<RenderInterpolationState>(body)
+                .unwrap()
+                .rendered,
+            None
+        );
+    }
+}
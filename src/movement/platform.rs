@@ -0,0 +1,261 @@
+use crate::physics_time::{PhysicsSchedule, PhysicsTime};
+use crate::{GameState, GameSystemSet};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+use bevy_tnua::TnuaProximitySensor;
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+/// Moves platforms and elevators along their waypoints.
+/// Platforms are velocity based kinematic bodies, so Tnua sees the velocity of the ground a character stands on
+/// and carries the character along.
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<MovingPlatform>()
+        .register_type::<PlatformMode>()
+        .register_type::<PlatformState>()
+        .add_systems(
+            Update,
+            spawn
+                .before(GameSystemSet::ColliderSpawn)
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            PhysicsSchedule,
+            (detect_riders, move_platforms)
+                .chain()
+                .before(PhysicsSet::SyncBackend),
+        );
+}
+
+/// A platform that follows a path of waypoints. Can be added in Blender.
+/// The path starts at the position the platform spawned at.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct MovingPlatform {
+    /// Offsets from the spawn position, visited in order
+    #[reflect(default)]
+    pub(crate) waypoints: Vec<Vec3>,
+    /// Speed in m/s
+    #[reflect(default)]
+    pub(crate) speed: f32,
+    #[reflect(default)]
+    pub(crate) mode: PlatformMode,
+    /// Seconds spent standing still at each waypoint
+    #[reflect(default)]
+    pub(crate) wait: f32,
+}
+
+impl Default for MovingPlatform {
+    fn default() -> Self {
+        Self {
+            waypoints: Vec::new(),
+            speed: 2.,
+            mode: default(),
+            wait: 1.,
+        }
+    }
+}
+
+/// What a platform does after reaching a waypoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize, Default)]
+pub(crate) enum PlatformMode {
+    /// Goes back to the start after the last waypoint
+    Loop,
+    /// Reverses at the first and the last waypoint
+    #[default]
+    PingPong,
+    /// Like [`PlatformMode::PingPong`], but stops at every waypoint until a character stands on it, like an elevator.
+    /// Riders that stay on after the wait are carried on to the next waypoint.
+    Triggered,
+}
+
+/// Progress of a [`MovingPlatform`] along its path. Part of the initial state of a replay.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component, Serialize, Deserialize)]
+pub(crate) struct PlatformState {
+    /// Absolute positions of the path, starting with the spawn position
+    path: Vec<Vec3>,
+    target: usize,
+    /// Whether the platform currently moves along the path in reverse
+    reversed: bool,
+    wait_remaining: f32,
+    /// Stopped at a waypoint until triggered
+    stopped: bool,
+    has_riders: bool,
+}
+
+impl PlatformState {
+    fn advance(&mut self, mode: PlatformMode) {
+        let last = self.path.len() - 1;
+        match mode {
+            PlatformMode::Loop => self.target = (self.target + 1) % self.path.len(),
+            PlatformMode::PingPong | PlatformMode::Triggered => {
+                if (self.reversed && self.target == 0) || (!self.reversed && self.target == last) {
+                    self.reversed = !self.reversed;
+                }
+                self.target = if self.reversed {
+                    self.target - 1
+                } else {
+                    self.target + 1
+                };
+            }
+        }
+    }
+}
+
+fn spawn(
+    platforms: Query<(Entity, &MovingPlatform, &Transform), Added<MovingPlatform>>,
+    mut commands: Commands,
+) {
+    for (entity, platform, transform) in platforms.iter() {
+        let origin = transform.translation;
+        let path: Vec<_> = iter::once(origin)
+            .chain(platform.waypoints.iter().map(|offset| origin + *offset))
+            .collect();
+        commands.entity(entity).insert((
+            RigidBody::KinematicVelocityBased,
+            Velocity::default(),
+            PlatformState {
+                // The platform already is at the first point of the path
+                target: 1,
+                path,
+                reversed: false,
+                wait_remaining: 0.,
+                stopped: platform.mode == PlatformMode::Triggered,
+                has_riders: false,
+            },
+        ));
+    }
+}
+
+/// Finds the platforms characters are standing on. The ground Tnua detects is usually a collider that is a child of the platform.
+fn detect_riders(
+    characters: Query<&TnuaProximitySensor>,
+    parents: Query<&Parent>,
+    mut platforms: Query<(Entity, &mut PlatformState)>,
+) {
+    let ridden: Vec<_> = characters
+        .iter()
+        .filter_map(|sensor| sensor.output.as_ref())
+        .filter_map(|output| {
+            iter::once(output.entity)
+                .chain(parents.iter_ancestors(output.entity))
+                .find(|&entity| platforms.contains(entity))
+        })
+        .collect();
+    for (entity, mut state) in platforms.iter_mut() {
+        let has_riders = ridden.contains(&entity);
+        if state.has_riders != has_riders {
+            state.has_riders = has_riders;
+        }
+    }
+}
+
+fn move_platforms(
+    time: Res<PhysicsTime>,
+    mut platforms: Query<(
+        &MovingPlatform,
+        &mut PlatformState,
+        &Transform,
+        &mut Velocity,
+    )>,
+) {
+    let dt = time.context().timestep.as_secs_f32();
+    for (platform, mut state, transform, mut velocity) in platforms.iter_mut() {
+        velocity.linvel = Vec3::ZERO;
+        velocity.angvel = Vec3::ZERO;
+        if state.path.len() < 2 {
+            continue;
+        }
+        if state.wait_remaining > 0. {
+            state.wait_remaining -= dt;
+            continue;
+        }
+        if state.stopped {
+            if !state.has_riders {
+                continue;
+            }
+            state.stopped = false;
+        }
+        let to_target = state.path[state.target] - transform.translation;
+        let step = platform.speed * dt;
+        if to_target.length() > step {
+            velocity.linvel = to_target.normalize() * platform.speed;
+            continue;
+        }
+        // Land exactly on the waypoint this tick
+        velocity.linvel = to_target / dt;
+        state.advance(platform.mode);
+        state.wait_remaining = platform.wait;
+        state.stopped = platform.mode == PlatformMode::Triggered;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::{TestApp, TestEntity};
+
+    #[test]
+    fn ping_pong_platform_returns_to_start() {
+        let mut app = TestApp::new();
+        let platform = app.spawn(TestEntity::platform(
+            Vec3::new(1., 0.25, 1.),
+            MovingPlatform {
+                waypoints: vec![Vec3::new(2., 0., 0.)],
+                speed: 4.,
+                mode: PlatformMode::PingPong,
+                wait: 0.,
+            },
+        ));
+
+        let arrived = app.tick_until(64, |app| app.translation(platform).x > 1.99);
+        assert!(arrived.is_some(), "Platform did not reach its waypoint");
+        let returned = app.tick_until(64, |app| app.translation(platform).x < 0.01);
+        assert!(returned.is_some(), "Platform did not return to its start");
+    }
+
+    fn elevator() -> TestEntity {
+        TestEntity::platform(
+            Vec3::new(2., 0.25, 2.),
+            MovingPlatform {
+                waypoints: vec![Vec3::new(0., 3., 0.)],
+                speed: 2.,
+                mode: PlatformMode::Triggered,
+                wait: 0.5,
+            },
+        )
+    }
+
+    #[test]
+    fn elevator_carries_rider_up_and_back_down() {
+        let mut app = TestApp::new();
+        app.spawn(elevator());
+        let player = app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+
+        let carried_up = app.tick_until(320, |app| app.translation(player).y > 3.);
+        assert!(
+            carried_up.is_some(),
+            "Player was not carried up by the elevator"
+        );
+
+        let carried_down = app.tick_until(320, |app| app.translation(player).y < 1.);
+        assert!(
+            carried_down.is_some(),
+            "Player staying on the elevator was not carried back down, but is at {}",
+            app.translation(player)
+        );
+    }
+
+    #[test]
+    fn elevator_waits_without_riders() {
+        let mut app = TestApp::new();
+        let platform = app.spawn(elevator());
+
+        app.ticks(128);
+
+        assert_eq!(app.translation(platform), Vec3::ZERO);
+    }
+}
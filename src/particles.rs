@@ -1,6 +1,7 @@
 use crate::{
     file_system_interaction::config::GameConfig,
     level_instantiation::on_spawn::Player,
+    movement::character_controller::SplashEvent,
     util::{F32Ext, Vec3Ext},
     GameState,
 };
@@ -14,10 +15,11 @@ mod creation;
 /// Handles particle effects instantiation and playing.
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<SprintingParticle>()
+        .register_type::<SplashParticle>()
         .add_plugins(HanabiPlugin)
         .add_systems(
             Update,
-            play_sprinting_effect.run_if(in_state(GameState::Playing)),
+            (play_sprinting_effect, play_splash_effect).run_if(in_state(GameState::Playing)),
         );
 }
 
@@ -25,6 +27,10 @@ pub(super) fn plugin(app: &mut App) {
 #[reflect(Component)]
 struct SprintingParticle;
 
+#[derive(Debug, Clone, Eq, PartialEq, Component, Reflect, Default)]
+#[reflect(Component)]
+struct SplashParticle;
+
 fn play_sprinting_effect(
     with_player: Query<&TnuaController, With<Player>>,
     mut with_particle: Query<&mut EffectSpawner, With<SprintingParticle>>,
@@ -43,3 +49,27 @@ fn play_sprinting_effect(
         }
     }
 }
+
+/// Bursts the splash effect of a character at the point where it broke through the surface.
+fn play_splash_effect(
+    mut splash_events: EventReader<SplashEvent>,
+    characters: Query<(&Transform, &Children)>,
+    mut with_particle: Query<
+        (&mut EffectSpawner, &mut Transform),
+        (With<SplashParticle>, Without<Children>),
+    >,
+) {
+    for splash in splash_events.read() {
+        let Ok((character_transform, children)) = characters.get(splash.character) else {
+            continue;
+        };
+        for &child in children.iter() {
+            let Ok((mut effect_spawner, mut transform)) = with_particle.get_mut(child) else {
+                continue;
+            };
+            // Characters only rotate around the up axis, so the height is the same in local space
+            transform.translation.y = splash.position.y - character_transform.translation.y;
+            effect_spawner.reset();
+        }
+    }
+}
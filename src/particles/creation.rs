@@ -1,4 +1,7 @@
-use crate::{level_instantiation::on_spawn::player, particles::SprintingParticle};
+use crate::{
+    level_instantiation::on_spawn::player,
+    particles::{SplashParticle, SprintingParticle},
+};
 use bevy::pbr::NotShadowReceiver;
 use bevy::prelude::*;
 use bevy_hanabi::prelude::*;
@@ -12,6 +15,15 @@ pub(crate) fn create_sprint_particle_bundle(effects: &mut Assets<EffectAsset>) -
     )
 }
 
+pub(crate) fn create_splash_particle_bundle(effects: &mut Assets<EffectAsset>) -> impl Bundle {
+    (
+        Name::new("Splash particle"),
+        SplashParticle,
+        ParticleEffectBundle::new(create_splash_effect(effects)),
+        NotShadowReceiver,
+    )
+}
+
 fn create_sprinting_effect(effects: &mut Assets<EffectAsset>) -> Handle<EffectAsset> {
     let mut color_gradient = Gradient::new();
     color_gradient.add_key(0.0, Vec4::new(1.2, 1.0, 1.0, 0.6));
@@ -68,3 +80,49 @@ fn create_sprinting_effect(effects: &mut Assets<EffectAsset>) -> Handle<EffectAs
         }),
     )
 }
+
+fn create_splash_effect(effects: &mut Assets<EffectAsset>) -> Handle<EffectAsset> {
+    let mut color_gradient = Gradient::new();
+    color_gradient.add_key(0.0, Vec4::new(0.8, 0.9, 1.2, 0.8));
+    color_gradient.add_key(0.5, Vec4::new(0.8, 0.9, 1.2, 0.5));
+    color_gradient.add_key(1.0, Vec4::new(0.8, 0.9, 1.2, 0.0));
+
+    let mut size_gradient = Gradient::new();
+    size_gradient.add_key(0.0, Vec2::splat(0.08));
+    size_gradient.add_key(1.0, Vec2::splat(0.03));
+
+    let mut module = Module::default();
+    let position_circle_modifier = SetPositionCircleModifier {
+        dimension: ShapeDimension::Surface,
+        radius: module.lit(player::RADIUS),
+        center: module.lit(Vec3::ZERO),
+        axis: module.lit(Vec3::Y),
+    };
+    let velocity_sphere_modifier = SetVelocitySphereModifier {
+        speed: module.lit(2.5),
+        center: module.lit(Vec3::new(0., -0.5, 0.)),
+    };
+    let lifetime = SetAttributeModifier::new(Attribute::LIFETIME, module.lit(0.6));
+    let orient_modifier = OrientModifier {
+        mode: OrientMode::FaceCameraPosition,
+        rotation: None,
+    };
+    let accel_modifier = AccelModifier::new(module.lit(Vec3::new(0., -9.81, 0.)));
+
+    effects.add(
+        EffectAsset::new(vec![64], Spawner::once(40.0.into(), false), module)
+            .with_name("Splash")
+            .init(position_circle_modifier)
+            .init(velocity_sphere_modifier)
+            .init(lifetime)
+            .update(accel_modifier)
+            .render(orient_modifier)
+            .render(ColorOverLifetimeModifier {
+                gradient: color_gradient,
+            })
+            .render(SizeOverLifetimeModifier {
+                gradient: size_gradient,
+                screen_space_size: false,
+            }),
+    )
+}
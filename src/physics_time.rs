@@ -1,8 +1,13 @@
+use std::collections::VecDeque;
 use std::time::Duration;
 
 use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
-use bevy::ecs::schedule::ScheduleLabel;
+use bevy::ecs::schedule::{BoxedCondition, ScheduleLabel};
 use bevy::prelude::*;
+use bevy_rapier3d::plugin::{RapierConfiguration, RapierContext, TimestepMode};
+use serde::{Deserialize, Serialize};
+
+use crate::file_system_interaction::config::GameConfig;
 
 /*
 
@@ -21,13 +26,102 @@ See the following link:
 // All diagnostics should have a unique DiagnosticPath. (https://github.com/bevyengine/bevy/blob/main/examples/diagnostics/custom_diagnostic.rs)
 pub const SYSTEM_ITERATION_COUNT: DiagnosticPath =
     DiagnosticPath::const_new("system_iteration_count");
+pub const DROPPED_TICK_COUNT: DiagnosticPath = DiagnosticPath::const_new("physics_dropped_ticks");
+pub const LATE_TICK_COUNT: DiagnosticPath = DiagnosticPath::const_new("physics_late_ticks");
+pub const BUDGET_SKIPPED_TICK_COUNT: DiagnosticPath =
+    DiagnosticPath::const_new("physics_budget_skipped_ticks");
+pub const TICK_TIME_MIN: DiagnosticPath = DiagnosticPath::const_new("physics_tick_time_min");
+pub const TICK_TIME_AVG: DiagnosticPath = DiagnosticPath::const_new("physics_tick_time_avg");
+pub const TICK_TIME_P99: DiagnosticPath = DiagnosticPath::const_new("physics_tick_time_p99");
+pub const OVERSTEP: DiagnosticPath = DiagnosticPath::const_new("physics_overstep");
+pub const RIGID_BODY_COUNT: DiagnosticPath = DiagnosticPath::const_new("physics_rigid_bodies");
+pub const COLLIDER_COUNT: DiagnosticPath = DiagnosticPath::const_new("physics_colliders");
+
+/// All diagnostics published by the [`TimePlugin`], in display order.
+pub const PHYSICS_DIAGNOSTICS: [DiagnosticPath; 10] = [
+    SYSTEM_ITERATION_COUNT,
+    TICK_TIME_MIN,
+    TICK_TIME_AVG,
+    TICK_TIME_P99,
+    OVERSTEP,
+    DROPPED_TICK_COUNT,
+    LATE_TICK_COUNT,
+    BUDGET_SKIPPED_TICK_COUNT,
+    RIGID_BODY_COUNT,
+    COLLIDER_COUNT,
+];
 
+/// Number of recent ticks the tick time statistics are computed over.
+const TICK_TIME_SAMPLES: usize = 256;
+
+/// Used until the [`GameConfig`] is loaded.
 pub const DEFAULT_TIMESTEP: Duration = Duration::from_micros(15625);
+/// Used until the [`GameConfig`] is loaded.
 pub const MAX_PHYSICS_EXEC_TIME: Duration = Duration::from_micros(15625);
+/// Used until the [`GameConfig`] is loaded.
+pub const DEFAULT_MAX_SUBSTEPS: u32 = 3;
 
 #[derive(Resource, Default)]
 pub struct DiagnosticFrameCount(u32);
 
+/// Ticks that were due but not run, counted since the last diagnostics report.
+#[derive(Resource, Default)]
+pub struct DiagnosticTickBacklog {
+    /// Ticks discarded by the [`OverstepPolicy`]
+    dropped: u32,
+    /// Ticks postponed to a later frame
+    late: u32,
+    /// Ticks that were due when [`PhysicsTimeInner::max_exec_time`] ran out.
+    /// These are not counted as dropped or late as well.
+    budget_skipped: u32,
+}
+
+impl DiagnosticTickBacklog {
+    /// Records the ticks left over after a frame, as returned by [`limit_overstep`].
+    fn record(&mut self, dropped: u32, late: u32, budget_exhausted: bool) {
+        if budget_exhausted {
+            self.budget_skipped += dropped + late;
+            self.late = 0;
+        } else {
+            self.dropped += dropped;
+            self.late = late;
+        }
+    }
+}
+
+/// Wall time spent on the most recent physics ticks.
+#[derive(Resource, Debug, Default)]
+pub struct PhysicsTickStats {
+    samples: VecDeque<Duration>,
+}
+
+impl PhysicsTickStats {
+    fn record(&mut self, tick_time: Duration) {
+        if self.samples.len() == TICK_TIME_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(tick_time);
+    }
+
+    pub fn min(&self) -> Option<Duration> {
+        self.samples.iter().min().copied()
+    }
+
+    pub fn average(&self) -> Option<Duration> {
+        let count = u32::try_from(self.samples.len()).ok().filter(|&n| n > 0)?;
+        Some(self.samples.iter().sum::<Duration>() / count)
+    }
+
+    /// The tick time that `percentile` percent of the recent ticks stayed below.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut sorted: Vec<_> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let last = sorted.len().checked_sub(1)?;
+        let index = ((percentile / 100.) * last as f64).round() as usize;
+        sorted.get(index.min(last)).copied()
+    }
+}
+
 pub struct TimePlugin;
 
 impl Plugin for TimePlugin {
@@ -36,11 +130,34 @@ impl Plugin for TimePlugin {
             .register_type::<PhysicsTime>()
             .init_resource::<PhysicsTime>()
             .init_resource::<DiagnosticFrameCount>()
+            .init_resource::<DiagnosticTickBacklog>()
+            .init_resource::<PhysicsTickStats>()
             // Diagnostics must be initialized before measurements can be added.
             .register_diagnostic(Diagnostic::new(SYSTEM_ITERATION_COUNT).with_suffix(" iterations"))
+            .register_diagnostic(Diagnostic::new(DROPPED_TICK_COUNT).with_suffix(" ticks"))
+            .register_diagnostic(Diagnostic::new(LATE_TICK_COUNT).with_suffix(" ticks"))
+            .register_diagnostic(Diagnostic::new(BUDGET_SKIPPED_TICK_COUNT).with_suffix(" ticks"))
+            .register_diagnostic(Diagnostic::new(TICK_TIME_MIN).with_suffix(" ms"))
+            .register_diagnostic(Diagnostic::new(TICK_TIME_AVG).with_suffix(" ms"))
+            .register_diagnostic(Diagnostic::new(TICK_TIME_P99).with_suffix(" ms"))
+            .register_diagnostic(Diagnostic::new(OVERSTEP).with_suffix(" ms"))
+            .register_diagnostic(Diagnostic::new(RIGID_BODY_COUNT).with_suffix(" bodies"))
+            .register_diagnostic(Diagnostic::new(COLLIDER_COUNT).with_suffix(" colliders"))
             .add_systems(PhysicsSchedule, diagnosics_count)
-            .add_systems(Update, diagnostics_report)
+            .add_systems(
+                Update,
+                (
+                    diagnostics_report,
+                    apply_config.run_if(resource_exists_and_changed::<GameConfig>),
+                ),
+            )
             .add_systems(PreUpdate, run_physics_schedule);
+        // Until the config is loaded, rapier would otherwise use the frame time for each tick.
+        // The `RapierPhysicsPlugin` is added before this plugin, so its configuration already exists.
+        let timestep = app.world.resource::<PhysicsTime>().context().timestep;
+        if let Some(mut rapier_config) = app.world.get_resource_mut::<RapierConfiguration>() {
+            rapier_config.timestep_mode = fixed_timestep_mode(timestep);
+        }
     }
 }
 
@@ -52,8 +169,15 @@ pub type PhysicsTime = Time<PhysicsTimeInner>;
 pub trait PhysicsTimeExt {
     fn pause(&mut self);
     fn resume(&mut self);
-    // fn step(&mut self);
-    // fn run(&mut self, speed: f32);
+    /// Runs exactly `ticks` physics ticks as fast as the per-frame limits allow, then pauses.
+    fn step(&mut self, ticks: u32);
+    /// Runs the simulation at `speed` times real time. Use values below 1 for slow-motion.
+    fn run(&mut self, speed: f32);
+    /// Changes the speed without resuming a paused simulation.
+    fn set_speed(&mut self, speed: f32);
+    fn is_paused(&self) -> bool;
+    /// The speed the simulation runs or would run at when resumed.
+    fn speed(&self) -> f32;
 }
 
 impl PhysicsTimeExt for PhysicsTime {
@@ -66,14 +190,69 @@ impl PhysicsTimeExt for PhysicsTime {
         self.context_mut().set_mode(old_mode);
     }
 
-    // fn step(&mut self) {
-    //     self.context_mut().set_mode(PhysicsTimeMode::OneTick);
-    // }
+    fn step(&mut self, ticks: u32) {
+        self.context_mut()
+            .set_mode(PhysicsTimeMode::Stepping { remaining: ticks });
+    }
+
+    fn run(&mut self, speed: f32) {
+        self.context_mut()
+            .set_mode(PhysicsTimeMode::Running { speed });
+    }
+
+    fn set_speed(&mut self, speed: f32) {
+        let context = self.context_mut();
+        if let PhysicsTimeMode::Running { .. } = context.mode {
+            context.set_mode(PhysicsTimeMode::Running { speed });
+        } else {
+            context.old_mode = PhysicsTimeMode::Running { speed };
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        !matches!(self.context().mode, PhysicsTimeMode::Running { .. })
+    }
+
+    fn speed(&self) -> f32 {
+        match self.context().old_mode {
+            PhysicsTimeMode::Running { speed } => speed,
+            _ => 1.,
+        }
+    }
+}
+
+/// Runs the physics simulation until a condition is met, then pauses it.
+/// The condition is evaluated after every physics tick.
+#[derive(Resource)]
+pub struct RunPhysicsUntil {
+    condition: BoxedCondition,
+    initialized: bool,
+}
+
+pub trait RunPhysicsUntilExt {
+    /// Runs the simulation at `speed` until `condition` returns `true` after a tick, then pauses it.
+    fn run_physics_until<M>(&mut self, speed: f32, condition: impl Condition<M>);
+}
+
+impl RunPhysicsUntilExt for World {
+    fn run_physics_until<M>(&mut self, speed: f32, condition: impl Condition<M>) {
+        start_run_until(self, speed, Box::new(IntoSystem::into_system(condition)));
+    }
+}
+
+impl RunPhysicsUntilExt for Commands<'_, '_> {
+    fn run_physics_until<M>(&mut self, speed: f32, condition: impl Condition<M>) {
+        let condition: BoxedCondition = Box::new(IntoSystem::into_system(condition));
+        self.add(move |world: &mut World| start_run_until(world, speed, condition));
+    }
+}
 
-    // fn run(&mut self, speed: f32) {
-    //     self.context_mut()
-    //         .set_mode(PhysicsTimeMode::Running { speed });
-    // }
+fn start_run_until(world: &mut World, speed: f32, condition: BoxedCondition) {
+    world.insert_resource(RunPhysicsUntil {
+        condition,
+        initialized: false,
+    });
+    world.resource_mut::<PhysicsTime>().run(speed);
 }
 
 #[derive(Debug, Copy, Clone, Reflect)]
@@ -83,6 +262,11 @@ pub struct PhysicsTimeInner {
     old_mode: PhysicsTimeMode,
     pub timestep: Duration,
     pub overstep: Duration,
+    /// Maximum number of ticks run in a single frame
+    pub max_substeps: u32,
+    /// Maximum wall time spent on ticks in a single frame
+    pub max_exec_time: Duration,
+    pub overstep_policy: OverstepPolicy,
 }
 
 impl PhysicsTimeInner {
@@ -101,15 +285,37 @@ impl Default for PhysicsTimeInner {
             old_mode: PhysicsTimeMode::default(),
             timestep: DEFAULT_TIMESTEP,
             overstep: Duration::ZERO,
+            max_substeps: DEFAULT_MAX_SUBSTEPS,
+            max_exec_time: MAX_PHYSICS_EXEC_TIME,
+            overstep_policy: OverstepPolicy::default(),
         }
     }
 }
 
+/// Decides what happens to ticks that were due, but could not be run in a frame
+/// because of [`PhysicsTimeInner::max_substeps`] or [`PhysicsTimeInner::max_exec_time`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize, Default)]
+#[reflect(Serialize, Deserialize)]
+pub enum OverstepPolicy {
+    /// Keep at most [`PhysicsTimeInner::max_substeps`] ticks for the next frames, drop the rest.
+    #[default]
+    Clamp,
+    /// Drop all ticks that could not be run this frame. The simulation slows down instead of catching up.
+    Drop,
+    /// Keep all ticks and catch up over the next frames. Can lead to a spiral of death on slow machines.
+    Keep,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Reflect)]
 pub enum PhysicsTimeMode {
     Paused,
-    OneTick,
-    Running { speed: f32 },
+    /// Runs the given number of ticks as fast as possible, then pauses.
+    Stepping {
+        remaining: u32,
+    },
+    Running {
+        speed: f32,
+    },
 }
 
 impl Default for PhysicsTimeMode {
@@ -122,7 +328,7 @@ fn accumulate_time(time: &mut PhysicsTime, delta: Duration) {
     let context = time.context_mut();
     match context.mode {
         PhysicsTimeMode::Paused => (),
-        PhysicsTimeMode::OneTick => (),
+        PhysicsTimeMode::Stepping { .. } => (),
         PhysicsTimeMode::Running { speed } => {
             if speed == std::f32::INFINITY {
                 context.overstep = Duration::MAX;
@@ -137,8 +343,18 @@ fn expend_time(time: &mut PhysicsTime) -> bool {
     let context = time.context_mut();
     let result = match context.mode {
         PhysicsTimeMode::Paused => false,
-        PhysicsTimeMode::OneTick => {
+        PhysicsTimeMode::Stepping { remaining: 0 } => {
             context.mode = PhysicsTimeMode::Paused;
+            false
+        }
+        PhysicsTimeMode::Stepping { remaining } => {
+            context.mode = if remaining > 1 {
+                PhysicsTimeMode::Stepping {
+                    remaining: remaining - 1,
+                }
+            } else {
+                PhysicsTimeMode::Paused
+            };
             context.overstep = Duration::ZERO;
             true
         }
@@ -159,9 +375,28 @@ fn expend_time(time: &mut PhysicsTime) -> bool {
     result
 }
 
-fn limit_overstep(time: &mut PhysicsTime) {
+/// Applies the [`OverstepPolicy`] to the ticks left over after a frame.
+/// Returns the number of dropped and late ticks.
+fn limit_overstep(time: &mut PhysicsTime) -> (u32, u32) {
     let context = time.context_mut();
-    context.overstep = context.overstep.min(context.timestep * 3);
+    let due_ticks = whole_ticks(context.overstep, context.timestep);
+    let remainder = context
+        .overstep
+        .saturating_sub(context.timestep.saturating_mul(due_ticks));
+    let kept_ticks = match context.overstep_policy {
+        OverstepPolicy::Clamp => due_ticks.min(context.max_substeps),
+        OverstepPolicy::Drop => 0,
+        OverstepPolicy::Keep => due_ticks,
+    };
+    context.overstep = context.timestep.saturating_mul(kept_ticks) + remainder;
+    (due_ticks - kept_ticks, kept_ticks)
+}
+
+fn whole_ticks(overstep: Duration, timestep: Duration) -> u32 {
+    if timestep.is_zero() {
+        return 0;
+    }
+    (overstep.as_nanos() / timestep.as_nanos()).min(u32::MAX as u128) as u32
 }
 
 pub fn run_physics_schedule(world: &mut World) {
@@ -169,17 +404,87 @@ pub fn run_physics_schedule(world: &mut World) {
     accumulate_time(&mut world.resource_mut::<PhysicsTime>(), delta);
 
     let time = std::time::Instant::now();
+    let (max_substeps, max_exec_time) = {
+        let context = world.resource::<PhysicsTime>().context();
+        let max_substeps = match context.mode {
+            // Only the time budget limits running at infinite speed
+            PhysicsTimeMode::Running { speed } if speed == f32::INFINITY => u32::MAX,
+            _ => context.max_substeps,
+        };
+        (max_substeps, context.max_exec_time)
+    };
     world.schedule_scope(PhysicsSchedule, |world, schedule| {
-        while expend_time(&mut world.resource_mut::<PhysicsTime>()) {
+        let mut ticks = 0;
+        let mut budget_exhausted = false;
+        while ticks < max_substeps && expend_time(&mut world.resource_mut::<PhysicsTime>()) {
+            let tick_start = std::time::Instant::now();
             schedule.run(world);
-            if time.elapsed() >= MAX_PHYSICS_EXEC_TIME {
+            world
+                .resource_mut::<PhysicsTickStats>()
+                .record(tick_start.elapsed());
+            ticks += 1;
+            if run_until_condition_met(world) {
+                break;
+            }
+            if time.elapsed() >= max_exec_time {
+                budget_exhausted = true;
                 break;
             }
         }
-        limit_overstep(&mut world.resource_mut::<PhysicsTime>());
+        let (dropped, late) = limit_overstep(&mut world.resource_mut::<PhysicsTime>());
+        world
+            .resource_mut::<DiagnosticTickBacklog>()
+            .record(dropped, late, budget_exhausted);
     });
 }
 
+fn apply_config(
+    config: Res<GameConfig>,
+    mut time: ResMut<PhysicsTime>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    let physics = &config.physics;
+    if physics.tick_rate <= 0. {
+        error!(
+            "Physics tick rate must be positive, but is {}",
+            physics.tick_rate
+        );
+        return;
+    }
+    let context = time.context_mut();
+    context.timestep = Duration::from_secs_f64(physics.tick_rate.recip());
+    context.max_substeps = physics.max_substeps.max(1);
+    context.max_exec_time = Duration::from_secs_f64(physics.max_catch_up_ms.max(0.) / 1000.);
+    context.overstep_policy = physics.overstep_policy;
+    rapier_config.timestep_mode = fixed_timestep_mode(context.timestep);
+}
+
+/// Makes rapier advance by exactly one timestep per physics tick.
+fn fixed_timestep_mode(timestep: Duration) -> TimestepMode {
+    TimestepMode::Fixed {
+        dt: timestep.as_secs_f32(),
+        substeps: 1,
+    }
+}
+
+/// Evaluates [`RunPhysicsUntil`] and pauses the simulation once its condition is met.
+fn run_until_condition_met(world: &mut World) -> bool {
+    let Some(mut run_until) = world.remove_resource::<RunPhysicsUntil>() else {
+        return false;
+    };
+    if !run_until.initialized {
+        run_until.condition.initialize(world);
+        run_until.initialized = true;
+    }
+    let met = run_until.condition.run((), world);
+    if met {
+        world.resource_mut::<PhysicsTime>().pause();
+    } else {
+        world.insert_resource(run_until);
+    }
+    met
+}
+
 fn diagnosics_count(mut frame_count: ResMut<DiagnosticFrameCount>) {
     frame_count.0 += 1;
 }
@@ -187,6 +492,10 @@ fn diagnosics_count(mut frame_count: ResMut<DiagnosticFrameCount>) {
 fn diagnostics_report(
     mut diagnostics: Diagnostics,
     mut frame_count: ResMut<DiagnosticFrameCount>,
+    mut backlog: ResMut<DiagnosticTickBacklog>,
+    tick_stats: Res<PhysicsTickStats>,
+    physics_time: Res<PhysicsTime>,
+    rapier_context: Option<Res<RapierContext>>,
     time: Res<Time<Real>>,
 ) {
     let delta = time.delta_seconds_f64();
@@ -194,5 +503,200 @@ fn diagnostics_report(
         return;
     }
     diagnostics.add_measurement(&SYSTEM_ITERATION_COUNT, || frame_count.0 as f64 / delta);
+    diagnostics.add_measurement(&DROPPED_TICK_COUNT, || backlog.dropped as f64);
+    diagnostics.add_measurement(&LATE_TICK_COUNT, || backlog.late as f64);
+    diagnostics.add_measurement(&BUDGET_SKIPPED_TICK_COUNT, || backlog.budget_skipped as f64);
+    let as_millis = |duration: Duration| duration.as_secs_f64() * 1000.;
+    if let Some(min) = tick_stats.min() {
+        diagnostics.add_measurement(&TICK_TIME_MIN, || as_millis(min));
+    }
+    if let Some(average) = tick_stats.average() {
+        diagnostics.add_measurement(&TICK_TIME_AVG, || as_millis(average));
+    }
+    if let Some(p99) = tick_stats.percentile(99.) {
+        diagnostics.add_measurement(&TICK_TIME_P99, || as_millis(p99));
+    }
+    diagnostics.add_measurement(&OVERSTEP, || as_millis(physics_time.context().overstep));
+    if let Some(rapier_context) = rapier_context {
+        diagnostics.add_measurement(&RIGID_BODY_COUNT, || rapier_context.bodies.len() as f64);
+        diagnostics.add_measurement(&COLLIDER_COUNT, || rapier_context.colliders.len() as f64);
+    }
     frame_count.0 = 0;
+    backlog.dropped = 0;
+    backlog.budget_skipped = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_harness::TestApp;
+
+    fn tick_stats(millis: impl IntoIterator<Item = u64>) -> PhysicsTickStats {
+        let mut stats = PhysicsTickStats::default();
+        for millis in millis {
+            stats.record(Duration::from_millis(millis));
+        }
+        stats
+    }
+
+    #[test]
+    fn percentile_of_no_ticks_is_none() {
+        let stats = PhysicsTickStats::default();
+
+        assert_eq!(stats.percentile(99.), None);
+        assert_eq!(stats.average(), None);
+    }
+
+    #[test]
+    fn percentile_of_single_tick_is_that_tick() {
+        let stats = tick_stats([5]);
+
+        for percentile in [0., 50., 99., 100.] {
+            assert_eq!(stats.percentile(percentile), Some(Duration::from_millis(5)));
+        }
+    }
+
+    #[test]
+    fn p99_ignores_the_slowest_percent() {
+        // Recorded out of order, as the percentile must not depend on it
+        let stats = tick_stats((1..=100).rev());
+
+        assert_eq!(stats.percentile(99.), Some(Duration::from_millis(99)));
+        assert_eq!(stats.percentile(0.), Some(Duration::from_millis(1)));
+        assert_eq!(stats.percentile(100.), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn tick_stats_only_keep_recent_ticks() {
+        let stats = tick_stats(1..=300);
+
+        assert_eq!(stats.min(), Some(Duration::from_millis(45)));
+    }
+
+    /// 5.5 ticks are due at a timestep of 10 ms
+    fn physics_time_with_overstep(policy: OverstepPolicy) -> PhysicsTime {
+        PhysicsTime::new_with(PhysicsTimeInner {
+            timestep: Duration::from_millis(10),
+            overstep: Duration::from_millis(55),
+            max_substeps: 3,
+            overstep_policy: policy,
+            ..default()
+        })
+    }
+
+    #[test]
+    fn clamp_policy_keeps_at_most_max_substeps() {
+        let mut time = physics_time_with_overstep(OverstepPolicy::Clamp);
+
+        assert_eq!(limit_overstep(&mut time), (2, 3));
+        assert_eq!(time.context().overstep, Duration::from_millis(35));
+    }
+
+    #[test]
+    fn clamp_policy_keeps_everything_below_max_substeps() {
+        let mut time = physics_time_with_overstep(OverstepPolicy::Clamp);
+        time.context_mut().overstep = Duration::from_millis(25);
+
+        assert_eq!(limit_overstep(&mut time), (0, 2));
+        assert_eq!(time.context().overstep, Duration::from_millis(25));
+    }
+
+    #[test]
+    fn drop_policy_keeps_only_the_partial_tick() {
+        let mut time = physics_time_with_overstep(OverstepPolicy::Drop);
+
+        assert_eq!(limit_overstep(&mut time), (5, 0));
+        assert_eq!(time.context().overstep, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn keep_policy_keeps_all_ticks() {
+        let mut time = physics_time_with_overstep(OverstepPolicy::Keep);
+
+        assert_eq!(limit_overstep(&mut time), (0, 5));
+        assert_eq!(time.context().overstep, Duration::from_millis(55));
+    }
+
+    #[test]
+    fn ticks_skipped_for_budget_are_not_counted_as_dropped_or_late() {
+        let mut backlog = DiagnosticTickBacklog::default();
+
+        backlog.record(2, 3, false);
+        backlog.record(1, 4, true);
+
+        assert_eq!(backlog.dropped, 2);
+        assert_eq!(backlog.late, 0);
+        assert_eq!(backlog.budget_skipped, 5);
+    }
+
+    #[test]
+    fn rapier_uses_fixed_timestep_before_config_is_loaded() {
+        let app = TestApp::new();
+
+        let timestep_mode = app
+            .app
+            .world
+            .resource::<RapierConfiguration>()
+            .timestep_mode;
+
+        assert!(
+            matches!(
+                timestep_mode,
+                TimestepMode::Fixed { dt, substeps: 1 } if dt == DEFAULT_TIMESTEP.as_secs_f32()
+            ),
+            "Rapier uses {timestep_mode:?}"
+        );
+    }
+
+    #[test]
+    fn config_sets_timestep_of_physics_and_rapier() {
+        let mut app = TestApp::with_config();
+        app.app.update();
+        let physics = app.app.world.resource::<GameConfig>().physics.clone();
+        let context = *app.app.world.resource::<PhysicsTime>().context();
+        assert_eq!(
+            context.timestep,
+            Duration::from_secs_f64(physics.tick_rate.recip())
+        );
+        assert_eq!(context.max_substeps, physics.max_substeps);
+        assert_eq!(context.overstep_policy, physics.overstep_policy);
+
+        app.config_mut().physics.tick_rate = 32.;
+        app.app.update();
+
+        let timestep = app.app.world.resource::<PhysicsTime>().context().timestep;
+        assert_eq!(timestep, Duration::from_secs_f64(1. / 32.));
+        let timestep_mode = app
+            .app
+            .world
+            .resource::<RapierConfiguration>()
+            .timestep_mode;
+        assert!(
+            matches!(
+                timestep_mode,
+                TimestepMode::Fixed { dt, substeps: 1 } if dt == timestep.as_secs_f32()
+            ),
+            "Rapier uses {timestep_mode:?}"
+        );
+    }
+
+    #[test]
+    fn run_physics_until_pauses_once_condition_holds() {
+        let mut app = TestApp::new();
+        let ticks = 5;
+        app.app
+            .world
+            .run_physics_until(1., move |time: Res<PhysicsTime>| {
+                time.elapsed() >= DEFAULT_TIMESTEP * ticks
+            });
+
+        for _ in 0..ticks * 4 {
+            app.app.update();
+        }
+
+        let time = app.app.world.resource::<PhysicsTime>();
+        assert!(time.is_paused());
+        assert_eq!(time.elapsed(), DEFAULT_TIMESTEP * ticks);
+        assert!(!app.app.world.contains_resource::<RunPhysicsUntil>());
+    }
 }
@@ -10,5 +10,10 @@ pub(crate) mod player_embodiment;
 /// - [`player_embodiment::plugin`]: Tells the components from [`super::movement::plugin`] about the desired [`actions::PlayerAction`]s.
 ///     Also handles other systems that change how the player is physically represented in the world.
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((actions::plugin, camera::plugin, player_embodiment::plugin));
+    app.add_plugins((headless_plugin, camera::plugin));
+}
+
+/// The parts of [`plugin`] that work without a rendered camera.
+pub(super) fn headless_plugin(app: &mut App) {
+    app.add_plugins((actions::plugin, player_embodiment::plugin));
 }
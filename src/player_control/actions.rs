@@ -41,16 +41,22 @@ pub(super) fn plugin(app: &mut App) {
         );
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Actionlike, Reflect, Default)]
+#[derive(
+    Debug, Clone, Copy, Eq, PartialEq, Hash, Actionlike, Reflect, Default, Serialize, Deserialize,
+)]
 pub(crate) enum PlayerAction {
     #[default]
     Move,
     Sprint,
     Jump,
+    Crouch,
+    Dash,
     Interact,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Actionlike, Reflect, Default)]
+#[derive(
+    Debug, Clone, Eq, PartialEq, Hash, Actionlike, Reflect, Default, Serialize, Deserialize,
+)]
 pub(crate) enum CameraAction {
     #[default]
     Orbit,
@@ -61,6 +67,11 @@ pub(crate) enum CameraAction {
 pub(crate) enum UiAction {
     #[default]
     TogglePause,
+    QuickSave,
+    QuickLoad,
+    TogglePhysicsPause,
+    StepPhysics,
+    ToggleReplayRecording,
 }
 
 pub(crate) fn create_player_action_input_manager_bundle() -> InputManagerBundle<PlayerAction> {
@@ -68,6 +79,8 @@ pub(crate) fn create_player_action_input_manager_bundle() -> InputManagerBundle<
         input_map: InputMap::new([
             (PlayerAction::Jump, KeyCode::Space),
             (PlayerAction::Sprint, KeyCode::ShiftLeft),
+            (PlayerAction::Crouch, KeyCode::ControlLeft),
+            (PlayerAction::Dash, KeyCode::AltLeft),
             (PlayerAction::Interact, KeyCode::KeyE),
         ])
         .insert(PlayerAction::Move, VirtualDPad::wasd())
@@ -88,7 +101,14 @@ pub(crate) fn create_camera_action_input_manager_bundle() -> InputManagerBundle<
 
 pub(crate) fn create_ui_action_input_manager_bundle() -> InputManagerBundle<UiAction> {
     InputManagerBundle {
-        input_map: InputMap::new([(UiAction::TogglePause, KeyCode::Escape)]),
+        input_map: InputMap::new([
+            (UiAction::TogglePause, KeyCode::Escape),
+            (UiAction::QuickSave, KeyCode::F5),
+            (UiAction::QuickLoad, KeyCode::F9),
+            (UiAction::TogglePhysicsPause, KeyCode::F7),
+            (UiAction::StepPhysics, KeyCode::F8),
+            (UiAction::ToggleReplayRecording, KeyCode::F10),
+        ]),
         ..default()
     }
 }
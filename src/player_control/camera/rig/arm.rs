@@ -1,5 +1,6 @@
 use crate::{
     file_system_interaction::config::GameConfig,
+    movement::physics::CollisionLayer,
     player_control::camera::{IngameCamera, IngameCameraKind},
 };
 use bevy::prelude::*;
@@ -63,9 +64,9 @@ fn get_distance_to_collision(
 
     let max_toi = camera.desired_distance;
     let solid = true;
-    //let filter = SpatialQueryFilter::from_mask(CollisionLayer::CameraObstacle.to_bits());
 
-    let filter = QueryFilter::from(CollisionGroups::new(Group::GROUP_4, Group::GROUP_4));
+    let obstacles = CollisionLayer::CameraObstacle.group();
+    let filter = QueryFilter::from(CollisionGroups::new(obstacles, obstacles));
 
     let min_distance = match camera.kind {
         IngameCameraKind::ThirdPerson => config.camera.third_person.min_distance_to_objects,
@@ -27,9 +27,14 @@ pub(super) fn plugin(app: &mut App) {
             Update,
             (
                 handle_jump,
+                handle_crouch,
+                handle_dash,
+                handle_climb,
                 handle_horizontal_movement,
                 rotate_to_speaker,
-                control_walking_sound.pipe(error),
+                control_walking_sound
+                    .pipe(error)
+                    .run_if(resource_exists::<AudioHandles>),
                 handle_camera_kind,
             )
                 .chain()
@@ -45,6 +50,30 @@ fn handle_jump(mut player_query: Query<(&ActionState<PlayerAction>, &mut Jump),
     }
 }
 
+fn handle_crouch(mut player_query: Query<(&ActionState<PlayerAction>, &mut Crouch), With<Player>>) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("handle_crouch").entered();
+    for (actions, mut crouch) in &mut player_query {
+        crouch.requested = actions.pressed(&PlayerAction::Crouch);
+    }
+}
+
+fn handle_dash(mut player_query: Query<(&ActionState<PlayerAction>, &mut Dash), With<Player>>) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("handle_dash").entered();
+    for (actions, mut dash) in &mut player_query {
+        dash.requested |= actions.just_pressed(&PlayerAction::Dash);
+    }
+}
+
+fn handle_climb(mut player_query: Query<(&ActionState<PlayerAction>, &mut Climb), With<Player>>) {
+    #[cfg(feature = "tracing")]
+    let _span = info_span!("handle_climb").entered();
+    for (actions, mut climb) in &mut player_query {
+        climb.mount_requested |= actions.just_pressed(&PlayerAction::Interact);
+    }
+}
+
 fn handle_horizontal_movement(
     mut player_query: Query<(&ActionState<PlayerAction>, &mut Walk, &mut Sprinting), With<Player>>,
     camera_query: Query<(&IngameCamera, &Transform), Without<Player>>,
@@ -73,7 +102,7 @@ fn handle_horizontal_movement(
             let is_looking_backward = forward.dot(forward_action) < 0.0;
             let is_first_person = camera.kind == IngameCameraKind::FirstPerson;
             let modifier = if is_looking_backward && is_first_person {
-                0.7
+                walk.backwards_modifier
             } else {
                 1.
             };
@@ -110,7 +139,7 @@ fn handle_camera_kind(
 
 fn rotate_to_speaker(
     dialog_target: Res<CurrentDialogTarget>,
-    mut with_player: Query<(&Transform, &mut TnuaController, &FloatHeight), With<Player>>,
+    mut with_player: Query<(&Transform, &mut TnuaController, &Walk, &FloatHeight), With<Player>>,
     speakers: Query<&Transform, Without<Player>>,
 ) {
     let Some(dialog_target) = dialog_target.0 else {
@@ -119,13 +148,13 @@ fn rotate_to_speaker(
 
     #[cfg(feature = "tracing")]
     let _span = info_span!("rotate_to_speaker").entered();
-    let (player_transform, mut controller, float_height) = single_mut!(with_player);
+    let (player_transform, mut controller, walk, float_height) = single_mut!(with_player);
     let speaker_transform = speakers.get(dialog_target).unwrap();
     let direction = (speaker_transform.translation - player_transform.translation).horizontal();
     controller.basis(TnuaBuiltinWalk {
         desired_forward: direction.normalize_or_zero(),
         float_height: float_height.0,
-        cling_distance: 0.1,
+        cling_distance: walk.cling_distance,
         ..Default::default()
     });
 }
@@ -143,6 +172,8 @@ fn control_walking_sound(
             .get_mut(&audio.walking)
             .context("Failed to get audio instance from handle")?;
         let Some((_, basis_state)) = controller.concrete_basis::<TnuaBuiltinWalk>() else {
+            // E.g. climbing
+            audio_instance.pause(default());
             continue;
         };
         let has_horizontal_movement = !basis_state.running_velocity.horizontal().is_approx_zero();
@@ -12,6 +12,7 @@ pub(super) fn plugin(app: &mut App) {
             GltfBlueprintsSet::AfterSpawn,
             YarnSpinnerSystemSet,
             GameSystemSet::ColliderSpawn,
+            GameSystemSet::NpcBehavior,
             GameSystemSet::Navigation,
             GameSystemSet::PlayerEmbodiment,
             GameSystemSet::GeneralMovement,
@@ -27,6 +28,7 @@ pub(super) fn plugin(app: &mut App) {
         (
             GameSystemSet::ColliderSpawn,
             GameSystemSet::UpdateInteractionOpportunities,
+            GameSystemSet::NpcBehavior,
             GameSystemSet::Navigation,
             GameSystemSet::PlayerEmbodiment,
             GameSystemSet::GeneralMovement,
@@ -40,7 +42,11 @@ pub(super) fn plugin(app: &mut App) {
 
     app.configure_sets(
         PostUpdate,
-        (GameSystemSet::CameraUpdate, DollyUpdateSet)
+        (
+            GameSystemSet::RenderInterpolation,
+            GameSystemSet::CameraUpdate,
+            DollyUpdateSet,
+        )
             .chain()
             .after(bevy_rapier3d::plugin::PhysicsSet::Writeback)
             .before(bevy::transform::TransformSystem::TransformPropagate)
@@ -56,6 +62,8 @@ pub(crate) enum GameSystemSet {
     /// Goes through entities tagged with `Collider` in Blender
     /// and inserts a proper XPBD collider
     ColliderSpawn,
+    /// Decide where NPCs want to go
+    NpcBehavior,
     /// Run path finding
     Navigation,
     /// Update interaction opportunities with the environment
@@ -66,6 +74,8 @@ pub(crate) enum GameSystemSet {
     GeneralMovement,
     /// Play animations
     PlayAnimation,
+    /// Move rigid bodies to their rendered pose between physics ticks
+    RenderInterpolation,
     /// Update the camera transform
     CameraUpdate,
     /// Interacts with Yarn Spinner for dialog logic
@@ -0,0 +1,373 @@
+//! A headless version of the [`GamePlugin`](crate::GamePlugin) for tests.
+//! It contains the gameplay plugins that don't need a window or a GPU and builds levels procedurally instead of loading them from Blender.
+//! Every [`TestApp::tick`] runs exactly one frame with exactly one physics tick, so tests are deterministic.
+
+use crate::{
+    file_system_interaction::config::GameConfig,
+    level_instantiation::{
+        self,
+        on_spawn::{DynamicProp, Npc, Player},
+    },
+    movement::{
+        self,
+        character_controller::{Climbable, WaterVolume},
+        physics::{CollisionLayer, CollisionLayers},
+        platform::MovingPlatform,
+    },
+    physics_time::{PhysicsTime, PhysicsTimeExt, DEFAULT_TIMESTEP},
+    player_control::{self, actions::ActionsFrozen, camera::IngameCamera},
+    system_set,
+    world_interaction::{
+        self, dialog::CurrentDialogTarget, interaction_ui::InteractionOpportunity,
+    },
+    GameState,
+};
+use bevy::{input::InputPlugin, prelude::*, scene::ScenePlugin, time::TimeUpdateStrategy};
+use bevy_common_assets::toml::TomlAssetPlugin;
+use bevy_hanabi::EffectAsset;
+use bevy_rapier3d::prelude::*;
+use bevy_yarnspinner::prelude::DialogueRunner;
+use oxidized_navigation::NavMeshAffector;
+use std::{fs, path::PathBuf};
+
+/// The config the game loads, relative to `assets/`
+const CONFIG_PATH: &str = "config/config.game.toml";
+/// Upper bound for frames spent waiting on assets, so that a broken asset fails the test instead of hanging it.
+const MAX_LOADING_UPDATES: u32 = 10_000;
+
+/// A fresh directory in the system's temp dir that is removed again when dropped.
+pub(crate) struct TempDir(pub(crate) PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("foxtrot-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Describes an entity of a procedurally built level, spawned by [`TestApp::spawn`].
+/// Box shaped entities are described by their half extents and have their top at the translation, except for props, which are centered on it.
+/// Characters only get their markers, so their character controller is added on the next tick, just like in a Blender level.
+pub(crate) struct TestEntity {
+    kind: TestEntityKind,
+    translation: Vec3,
+    half_extents: Vec3,
+    components: Vec<Box<dyn FnOnce(&mut EntityWorldMut)>>,
+}
+
+enum TestEntityKind {
+    Ground,
+    Prop,
+    Platform(MovingPlatform),
+    Water,
+    Climbable,
+    Player,
+    Npc,
+}
+
+impl TestEntity {
+    fn new(kind: TestEntityKind, half_extents: Vec3) -> Self {
+        Self {
+            kind,
+            translation: Vec3::ZERO,
+            half_extents,
+            components: Vec::new(),
+        }
+    }
+
+    /// A flat static ground that affects the navmesh, centered at the origin.
+    pub(crate) fn ground(half_extent: f32) -> Self {
+        Self::new(
+            TestEntityKind::Ground,
+            Vec3::new(half_extent, 0.5, half_extent),
+        )
+    }
+
+    /// A cube shaped [`DynamicProp`].
+    pub(crate) fn prop(half_extent: f32) -> Self {
+        Self::new(TestEntityKind::Prop, Vec3::splat(half_extent))
+    }
+
+    pub(crate) fn platform(half_extents: Vec3, platform: MovingPlatform) -> Self {
+        Self::new(TestEntityKind::Platform(platform), half_extents)
+    }
+
+    pub(crate) fn water(half_extents: Vec3) -> Self {
+        Self::new(TestEntityKind::Water, half_extents)
+    }
+
+    /// A solid [`Climbable`] box.
+    pub(crate) fn climbable(half_extents: Vec3) -> Self {
+        Self::new(TestEntityKind::Climbable, half_extents)
+    }
+
+    pub(crate) fn player() -> Self {
+        Self::new(TestEntityKind::Player, Vec3::ZERO)
+    }
+
+    pub(crate) fn npc() -> Self {
+        Self::new(TestEntityKind::Npc, Vec3::ZERO)
+    }
+
+    pub(crate) fn at(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+
+    /// Adds components to the spawned entity, e.g. a [`YarnNode`](crate::world_interaction::dialog::YarnNode) for a dialog the player can start.
+    pub(crate) fn with(mut self, bundle: impl Bundle) -> Self {
+        self.components.push(Box::new(move |entity| {
+            entity.insert(bundle);
+        }));
+        self
+    }
+}
+
+/// Spawns a parent with the marker and a child with the collider, like the objects of a Blender level.
+fn spawn_with_collider<'w>(
+    world: &'w mut World,
+    name: &str,
+    marker: impl Bundle,
+    translation: Vec3,
+    collider: impl Bundle,
+) -> EntityWorldMut<'w> {
+    let mut entity = world.spawn((
+        Name::new(name.to_string()),
+        marker,
+        SpatialBundle::from_transform(Transform::from_translation(translation)),
+    ));
+    entity.with_children(|parent| {
+        parent.spawn((Name::new(format!("{name} Collider")), collider));
+    });
+    entity
+}
+
+pub(crate) struct TestApp {
+    pub(crate) app: App,
+}
+
+impl TestApp {
+    /// Creates an app in [`GameState::Playing`] with a camera looking along -Z, but without any level.
+    pub(crate) fn new() -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            InputPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+        ))
+        // Needed by rapier's and the player's spawn systems
+        .init_asset::<Mesh>()
+        .init_asset::<EffectAsset>()
+        // Tnua and the physics see the same frame time as in a game running at the tick rate
+        .insert_resource(TimeUpdateStrategy::ManualDuration(DEFAULT_TIMESTEP))
+        .insert_state(GameState::Playing)
+        .add_plugins((
+            system_set::plugin,
+            movement::plugin,
+            player_control::headless_plugin,
+            world_interaction::headless_plugin,
+            level_instantiation::headless_plugin,
+        ));
+        app.world.spawn((
+            Name::new("Camera"),
+            IngameCamera::default(),
+            TransformBundle::from_transform(
+                Transform::from_xyz(0., 10., 10.).looking_at(Vec3::ZERO, Vec3::Y),
+            ),
+        ));
+        // Physics only advances when a test asks for it
+        app.world.resource_mut::<PhysicsTime>().pause();
+        Self { app }
+    }
+
+    /// Like [`TestApp::new`], but with the [`GameConfig`] of the game, so that its tuning is applied like in a real game.
+    /// Without it, every component keeps its default.
+    pub(crate) fn with_config() -> Self {
+        let mut test_app = Self::new();
+        let app = &mut test_app.app;
+        app.add_plugins(TomlAssetPlugin::<GameConfig>::new(&["game.toml"]));
+        let handle: Handle<GameConfig> = app.world.resource::<AssetServer>().load(CONFIG_PATH);
+        for _ in 0..MAX_LOADING_UPDATES {
+            if let Some(config) = app.world.resource::<Assets<GameConfig>>().get(&handle) {
+                let config = config.clone();
+                app.insert_resource(config);
+                return test_app;
+            }
+            app.update();
+        }
+        panic!("{CONFIG_PATH} was not loaded after {MAX_LOADING_UPDATES} updates");
+    }
+
+    pub(crate) fn config_mut(&mut self) -> Mut<GameConfig> {
+        self.app.world.resource_mut::<GameConfig>()
+    }
+
+    /// Spawns an entity of the procedurally built level.
+    pub(crate) fn spawn(&mut self, entity: TestEntity) -> Entity {
+        let TestEntity {
+            kind,
+            translation,
+            half_extents,
+            components,
+        } = entity;
+        let world = &mut self.app.world;
+        let collider = Collider::cuboid(half_extents.x, half_extents.y, half_extents.z);
+        // Colliders hang below their parent, so that the top of the box is at the translation
+        let below = TransformBundle::from_transform(Transform::from_xyz(0., -half_extents.y, 0.));
+        let mut entity = match kind {
+            TestEntityKind::Ground => world.spawn((
+                Name::new("Ground"),
+                TransformBundle::from_transform(Transform::from_translation(
+                    translation - Vec3::Y * half_extents.y,
+                )),
+                RigidBody::Fixed,
+                collider,
+                CollisionLayers::terrain(),
+                NavMeshAffector,
+            )),
+            TestEntityKind::Prop => spawn_with_collider(
+                world,
+                "Prop",
+                DynamicProp::default(),
+                translation,
+                (
+                    TransformBundle::default(),
+                    collider,
+                    CollisionLayers::prop(),
+                ),
+            ),
+            TestEntityKind::Platform(platform) => spawn_with_collider(
+                world,
+                "Platform",
+                platform,
+                translation,
+                (below, collider, CollisionLayers::terrain()),
+            ),
+            TestEntityKind::Water => spawn_with_collider(
+                world,
+                "Water",
+                WaterVolume::default(),
+                translation,
+                (
+                    below,
+                    collider,
+                    Sensor,
+                    CollisionLayers::new(CollisionLayer::Sensor, CollisionLayer::Player),
+                ),
+            ),
+            TestEntityKind::Climbable => spawn_with_collider(
+                world,
+                "Climbable",
+                Climbable,
+                translation,
+                (below, collider, CollisionLayers::terrain()),
+            ),
+            TestEntityKind::Player => world.spawn((
+                Name::new("Player"),
+                Player,
+                SpatialBundle::from_transform(Transform::from_translation(translation)),
+            )),
+            TestEntityKind::Npc => world.spawn((
+                Name::new("NPC"),
+                Npc,
+                SpatialBundle::from_transform(Transform::from_translation(translation)),
+            )),
+        };
+        for insert in components {
+            insert(&mut entity);
+        }
+        entity.id()
+    }
+
+    /// Runs a single frame containing exactly one physics tick.
+    pub(crate) fn tick(&mut self) {
+        self.app.world.resource_mut::<PhysicsTime>().step(1);
+        self.app.update();
+    }
+
+    pub(crate) fn ticks(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    /// Ticks until `condition` holds, returning the number of ticks it took, or `None` if it didn't hold after `max_ticks`.
+    pub(crate) fn tick_until(
+        &mut self,
+        max_ticks: u32,
+        mut condition: impl FnMut(&mut Self) -> bool,
+    ) -> Option<u32> {
+        for tick in 0..=max_ticks {
+            if condition(self) {
+                return Some(tick);
+            }
+            self.tick();
+        }
+        None
+    }
+
+    /// Runs frames without ticking the physics until the dialogue runner was created from the yarn files.
+    pub(crate) fn wait_for_dialogue_runner(&mut self) {
+        for _ in 0..MAX_LOADING_UPDATES {
+            if self.dialogue_runner().is_some() {
+                return;
+            }
+            self.app.update();
+        }
+        panic!("The yarn project was not loaded after {MAX_LOADING_UPDATES} updates");
+    }
+
+    /// Holds a key down until [`TestApp::release`] is called.
+    pub(crate) fn press(&mut self, key: KeyCode) {
+        self.app
+            .world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(key);
+    }
+
+    pub(crate) fn release(&mut self, key: KeyCode) {
+        self.app
+            .world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(key);
+    }
+
+    pub(crate) fn translation(&self, entity: Entity) -> Vec3 {
+        self.app
+            .world
+            .get::<Transform>(entity)
+            .expect("Entity has no transform")
+            .translation
+    }
+
+    pub(crate) fn interaction_opportunity(&self) -> Option<Entity> {
+        self.app.world.resource::<InteractionOpportunity>().0
+    }
+
+    pub(crate) fn dialog_target(&self) -> Option<Entity> {
+        self.app.world.resource::<CurrentDialogTarget>().0
+    }
+
+    pub(crate) fn actions_frozen(&self) -> bool {
+        self.app.world.resource::<ActionsFrozen>().is_frozen()
+    }
+
+    pub(crate) fn dialogue_runner(&mut self) -> Option<&DialogueRunner> {
+        self.app
+            .world
+            .query::<&DialogueRunner>()
+            .get_single(&self.app.world)
+            .ok()
+    }
+}
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
 pub(crate) mod dialog;
-mod interaction_ui;
+pub(crate) mod interaction_ui;
 
 /// Handles player to world interactions. Split into the following sub-plugins:
 /// - [`dialog::plugin`] handles dialog trees
@@ -9,3 +9,9 @@ mod interaction_ui;
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((dialog::plugin, interaction_ui::plugin));
 }
+
+/// The parts of [`plugin`] that work without a window.
+#[cfg(test)]
+pub(super) fn headless_plugin(app: &mut App) {
+    app.add_plugins((dialog::headless_plugin, interaction_ui::headless_plugin));
+}
@@ -9,20 +9,25 @@ use serde::{Deserialize, Serialize};
 pub(super) fn plugin(app: &mut App) {
     app.add_plugins((
         EguiPlugin,
-        YarnSpinnerPlugin::new(),
+        headless_plugin,
         ExampleYarnSpinnerDialogueViewPlugin::new(),
-    ))
-    .add_systems(
-        Update,
-        (
-            spawn_dialogue_runner.run_if(resource_added::<YarnProject>),
-            unfreeze_after_dialog.in_set(GameSystemSet::Dialog),
+    ));
+}
+
+/// The dialog logic without the dialogue view.
+pub(super) fn headless_plugin(app: &mut App) {
+    app.add_plugins(YarnSpinnerPlugin::new())
+        .add_systems(
+            Update,
+            (
+                spawn_dialogue_runner.run_if(resource_added::<YarnProject>),
+                unfreeze_after_dialog.in_set(GameSystemSet::Dialog),
+            )
+                .chain(),
         )
-            .chain(),
-    )
-    .init_resource::<CurrentDialogTarget>()
-    .register_type::<YarnNode>()
-    .register_type::<CurrentDialogTarget>();
+        .init_resource::<CurrentDialogTarget>()
+        .register_type::<YarnNode>()
+        .register_type::<CurrentDialogTarget>();
 }
 
 #[derive(Component, Debug, Clone, Eq, PartialEq, Reflect, Serialize, Deserialize)]
@@ -19,20 +19,32 @@ use std::f32::consts::TAU;
 use std::iter;
 
 pub(super) fn plugin(app: &mut App) {
+    app.add_plugins(headless_plugin).add_systems(
+        Update,
+        display_interaction_prompt
+            .after(update_interaction_opportunities)
+            .in_set(GameSystemSet::UpdateInteractionOpportunities)
+            .run_if(not(is_frozen)),
+    );
+}
+
+/// Finding interaction opportunities and starting dialogs, without the prompt.
+pub(super) fn headless_plugin(app: &mut App) {
     app.register_type::<InteractionOpportunity>()
         .init_resource::<InteractionOpportunity>()
         .add_systems(
             Update,
-            (update_interaction_opportunities, display_interaction_prompt)
+            (update_interaction_opportunities, start_dialog)
                 .chain()
                 .in_set(GameSystemSet::UpdateInteractionOpportunities)
                 .run_if(not(is_frozen)),
         );
 }
 
+/// The entity the player can currently interact with, if any.
 #[derive(Debug, Clone, Eq, PartialEq, Resource, Reflect, Serialize, Deserialize, Default)]
 #[reflect(Resource, Serialize, Deserialize)]
-struct InteractionOpportunity(Option<Entity>);
+pub(crate) struct InteractionOpportunity(pub(crate) Option<Entity>);
 
 fn update_interaction_opportunities(
     player_query: Query<(&GlobalTransform, &CollidingEntities), With<Player>>,
@@ -91,21 +103,13 @@ fn is_facing_target(
 
 fn display_interaction_prompt(
     interaction_opportunity: Res<InteractionOpportunity>,
-    mut dialogue_runner: Query<&mut DialogueRunner>,
     mut egui_contexts: EguiContexts,
-    actions: Query<&ActionState<PlayerAction>>,
     primary_windows: Query<&Window, With<PrimaryWindow>>,
-    dialog_target_query: Query<(Entity, &YarnNode)>,
-    mut freeze: ResMut<ActionsFrozen>,
-    mut current_dialog_target: ResMut<CurrentDialogTarget>,
 ) {
-    let Some(opportunity) = interaction_opportunity.0 else {
+    if interaction_opportunity.0.is_none() {
         return;
-    };
+    }
     let window = single!(primary_windows);
-    let mut dialogue_runner = single_mut!(dialogue_runner);
-
-    let (entity, dialog_target) = dialog_target_query.get(opportunity).unwrap();
     egui::Window::new("Interaction")
         .collapsible(false)
         .title_bar(false)
@@ -114,6 +118,22 @@ fn display_interaction_prompt(
         .show(egui_contexts.ctx_mut(), |ui| {
             ui.label("E: Talk");
         });
+}
+
+fn start_dialog(
+    interaction_opportunity: Res<InteractionOpportunity>,
+    mut dialogue_runner: Query<&mut DialogueRunner>,
+    actions: Query<&ActionState<PlayerAction>>,
+    dialog_target_query: Query<(Entity, &YarnNode)>,
+    mut freeze: ResMut<ActionsFrozen>,
+    mut current_dialog_target: ResMut<CurrentDialogTarget>,
+) {
+    let Some(opportunity) = interaction_opportunity.0 else {
+        return;
+    };
+    let mut dialogue_runner = single_mut!(dialogue_runner);
+
+    let (entity, dialog_target) = dialog_target_query.get(opportunity).unwrap();
     for actions in actions.iter() {
         if actions.just_pressed(&PlayerAction::Interact) {
             dialogue_runner.start_node(&dialog_target.0);
@@ -122,3 +142,53 @@ fn display_interaction_prompt(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::test_harness::{TestApp, TestEntity};
+    use crate::world_interaction::dialog::YarnNode;
+    use bevy::prelude::*;
+
+    #[test]
+    fn talking_to_npc_in_front_starts_dialog() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        let npc = app.spawn(
+            TestEntity::npc()
+                .at(Vec3::new(0., 1., -1.5))
+                .with(YarnNode("Follower".to_string())),
+        );
+        app.wait_for_dialogue_runner();
+
+        let found = app.tick_until(120, |app| app.interaction_opportunity() == Some(npc));
+        assert!(
+            found.is_some(),
+            "NPC never became an interaction opportunity"
+        );
+
+        app.press(KeyCode::KeyE);
+        app.tick();
+
+        assert_eq!(app.dialog_target(), Some(npc));
+        assert!(app.actions_frozen());
+        assert!(app.dialogue_runner().unwrap().is_running());
+    }
+
+    #[test]
+    fn npc_behind_player_cannot_be_talked_to() {
+        let mut app = TestApp::new();
+        app.spawn(TestEntity::ground(20.));
+        app.spawn(TestEntity::player().at(Vec3::new(0., 1., 0.)));
+        app.spawn(
+            TestEntity::npc()
+                .at(Vec3::new(0., 1., 1.5))
+                .with(YarnNode("Follower".to_string())),
+        );
+        app.wait_for_dialogue_runner();
+
+        app.ticks(120);
+
+        assert_eq!(app.interaction_opportunity(), None);
+    }
+}